@@ -21,14 +21,22 @@ function run_test_json {
 	fi
 }
 
-function run_test_scip {
+# Generates the SCIP index of the test project
+function index_test_scip {
 	pushd test-projects/$1
 	rm -rf target
 	rust-analyzer scip . --output target/index.scip
 	popd
-	cargo run test-projects/$1/target/index.scip > test-projects/$1/target/$1.stdout
-	output=$(cat test-projects/$1/target/$1.stdout)
-	expected=$(cat test-projects/$1/$1.stdout)
+}
+
+# Runs warnalyzer on the index of the test project, with the additional
+# arguments, and compares the output with the expected one in the given file.
+# The additional arguments come first, so they can start with a subcommand.
+function check_test_scip {
+	# Absolute paths depend on where the repository is checked out
+	cargo run -- "${@:3}" test-projects/$1/target/index.scip | sed "s|$PWD/||g" > test-projects/$1/target/$2
+	output=$(cat test-projects/$1/target/$2)
+	expected=$(cat test-projects/$1/$2)
 	if [ "$expected" != "$output" ]; then
 		echo "Mismatch. Expected:"
 		echo "---------------------"
@@ -38,10 +46,77 @@ function run_test_scip {
 		echo "---------------------"
 		echo "$output"
 		echo "---------------------"
-		#exit 1 # TODO turn this back on
+		exit 1
 	fi
 }
 
+# Runs warnalyzer on the index of the test project, with the additional
+# arguments, and checks that it exits with the given code
+function check_test_scip_exit_code {
+	cargo run -- "${@:3}" test-projects/$1/target/index.scip > /dev/null
+	code=$?
+	if [ "$code" != "$2" ]; then
+		echo "Expected exit code $2, but got $code"
+		exit 1
+	fi
+}
+
+# Additional arguments are passed to warnalyzer
+function run_test_scip {
+	index_test_scip $1
+	check_test_scip $1 $1.stdout "${@:2}"
+}
+
 run_test_scip test01
+check_test_scip test01 recurse.stdout --recurse
+check_test_scip test01 dump.stdout dump --kind Struct
+check_test_scip test01 json.stdout --format json
+check_test_scip test01 jsonl.stdout --format jsonl
+check_test_scip test01 sarif.stdout --format sarif
+check_test_scip test01 baseline.stdout --baseline test-projects/test01/baseline.json
+check_test_scip test01 explain.stdout explain library-thing/src/lib.rs:14:4 --recurse
+check_test_scip test01 explain-symbol.stdout explain 'rust-analyzer cargo library-thing 0.1.0 used_fn().' --recurse
+check_test_scip_exit_code test01 0
+check_test_scip_exit_code test01 1 --deny
+check_test_scip_exit_code test01 0 --max-findings 3
+check_test_scip_exit_code test01 1 --max-findings 2
+check_test_scip_exit_code test01 1 --max-findings-of function=0
+check_test_scip_exit_code test01 0 --max-findings-of unused-struct=1
+check_test_scip_exit_code test01 2 --config test-projects/test01/missing.toml
 
 run_test_scip test02
+
+run_test_scip test03
+
+run_test_scip test04 --public-api library-thing
+
+run_test_scip test05 --check-visibility
+check_test_scip test05 package.stdout --check-visibility --package binary-thing
+check_test_scip test05 exclude.stdout --check-visibility --exclude binary-thing
+
+run_test_scip test06 --test-only --public-api library-thing
+check_test_scip test06 note.stdout --test-only --public-api library-thing --test-only-severity note
+
+run_test_scip test07
+check_test_scip test07 test-only.stdout --test-only --test-only-severity note
+check_test_scip test07 other.stdout --config test-projects/test07/other.toml
+
+run_test_scip test08
+
+run_test_scip test09
+check_test_scip test09 recurse.stdout --recurse
+
+run_test_scip test10
+
+run_test_scip test11
+
+# The indexes of both workspaces are analyzed together
+index_test_scip test12/library-workspace
+index_test_scip test12/binary-workspace
+check_test_scip test12/binary-workspace test12.stdout test-projects/test12/library-workspace/target/index.scip
+
+run_test_scip test13 --root 'library_thing::callbacks*'
+check_test_scip test13 recurse.stdout --root 'regex:.*on_st(art|op)' --recurse
+check_test_scip test13 explain.stdout explain library-thing/src/lib.rs:2:19
+
+run_test_scip test14
@@ -0,0 +1,80 @@
+use crate::{finding::Finding, Error};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+/// A set of known findings, which should not be reported again
+///
+/// Findings are identified by their stable key, which is based on the
+/// SCIP symbol if available, so moving code around doesn't invalidate it.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Baseline {
+	pub entries: Vec<BaselineEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BaselineEntry {
+	pub key: String,
+	/// Location of the finding at the time it was recorded, for humans only
+	pub location: String,
+	pub message: String,
+}
+
+impl Baseline {
+	pub fn from_findings(findings: &[Finding]) -> Self {
+		let entries = findings.iter()
+			.map(|finding| BaselineEntry {
+				key: finding.stable_key(),
+				location: finding.span.display_str(),
+				message: finding.message(),
+			})
+			.collect();
+		Baseline {
+			entries,
+		}
+	}
+	pub fn load(path: &Path) -> Result<Self, Error> {
+		let file = std::fs::read_to_string(path)?;
+		let baseline = serde_json::from_str(&file)?;
+		Ok(baseline)
+	}
+	pub fn save(&self, path: &Path) -> Result<(), Error> {
+		let mut file = serde_json::to_string_pretty(self)?;
+		file.push('\n');
+		std::fs::write(path, file)?;
+		Ok(())
+	}
+	/// Removes the findings contained in the baseline
+	///
+	/// Returns the new findings, and the entries of the baseline that weren't found any more.
+	/// Each entry only covers one finding, so that additional findings with the same key
+	/// are still reported.
+	pub fn apply(&self, findings: Vec<Finding>) -> (Vec<Finding>, Vec<&BaselineEntry>) {
+		let mut remaining = HashMap::<_, usize>::new();
+		for entry in &self.entries {
+			*remaining.entry(entry.key.as_str()).or_default() += 1;
+		}
+		let new = findings.into_iter()
+			.filter(|finding| {
+				match remaining.get_mut(finding.stable_key().as_str()) {
+					Some(count) if *count > 0 => {
+						*count -= 1;
+						false
+					},
+					_ => true,
+				}
+			})
+			.collect();
+		let gone = self.entries.iter()
+			.filter(|entry| {
+				match remaining.get_mut(entry.key.as_str()) {
+					Some(count) if *count > 0 => {
+						*count -= 1;
+						true
+					},
+					_ => false,
+				}
+			})
+			.collect();
+		(new, gone)
+	}
+}
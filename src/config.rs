@@ -0,0 +1,161 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::RegexSet;
+use serde::Deserialize;
+
+use crate::{finding::{self, Severity}, metadata::Metadata, output::OutputFormat, Error};
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+/// The name of the configuration file in the workspace root
+pub const FILE_NAME: &str = "warnalyzer.toml";
+
+/// Project policy, read from `warnalyzer.toml` or `[workspace.metadata.warnalyzer]`
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+	/// Workspace members to report findings in, all of them if empty
+	pub packages: Vec<String>,
+	/// Workspace members to not report findings in
+	pub exclude: Vec<String>,
+	/// Glob patterns of files to not report findings in
+	pub ignore_paths: Vec<String>,
+	/// Patterns of definitions to not report findings for
+	pub ignore_symbols: Vec<String>,
+	/// Severities by category like `function`, or by rule id like `unused-function`
+	pub severity: BTreeMap<String, Severity>,
+	/// Patterns of definitions that are used implicitly, like through FFI
+	pub roots: Vec<String>,
+	/// Packages whose public API is used from outside of the workspace
+	pub public_api: Vec<String>,
+	pub output: OutputConfig,
+}
+
+/// Defaults for options of the command line
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct OutputConfig {
+	pub format: Option<OutputFormat>,
+	/// Baseline file, relative to the workspace root, or to the configuration file
+	pub baseline: Option<PathBuf>,
+}
+
+impl Config {
+	/// Reads the configuration of the workspace
+	///
+	/// `warnalyzer.toml` in the workspace root takes precedence over the
+	/// `[workspace.metadata.warnalyzer]` table of the workspace's manifest.
+	pub fn for_workspace(metadata: &Metadata) -> Result<Self, Error> {
+		let root = &metadata.workspace_root;
+		let path = root.join(FILE_NAME);
+		let table = metadata.workspace_metadata.as_ref()
+			.and_then(|m| m.get("warnalyzer"));
+		if path.exists() {
+			if table.is_some() {
+				warn!("Ignoring [workspace.metadata.warnalyzer] in favour of {}", path.display());
+			}
+			return Self::from_file(&path);
+		}
+		let Some(table) = table else {
+			return Ok(Config::default())
+		};
+		let mut config = Config::deserialize(table)
+			.map_err(|e| Error::Config {
+				path: root.join("Cargo.toml"),
+				message: format!("[workspace.metadata.warnalyzer]: {e}"),
+			})?;
+		config.validate(&root.join("Cargo.toml"))?;
+		config.output.baseline = config.output.baseline.map(|p| root.join(p));
+		Ok(config)
+	}
+	/// Reads a configuration file, paths in it are relative to the file
+	pub fn from_file(path: &Path) -> Result<Self, Error> {
+		let file = std::fs::read_to_string(path)?;
+		let mut config: Config = toml::from_str(&file)
+			.map_err(|e| Error::Config {
+				path: path.to_owned(),
+				message: e.to_string(),
+			})?;
+		config.validate(path)?;
+		let dir = path.parent().unwrap_or(Path::new(""));
+		config.output.baseline = config.output.baseline.map(|p| dir.join(p));
+		Ok(config)
+	}
+	/// Checks what serde can't, like whether the keys of the severities are known
+	fn validate(&self, path: &Path) -> Result<(), Error> {
+		for key in self.severity.keys() {
+			if !finding::is_known_category(key) {
+				return Err(Error::Config {
+					path: path.to_owned(),
+					message: format!("unknown category or rule id '{key}' in severity"),
+				});
+			}
+		}
+		for patterns in [&self.ignore_paths, &self.ignore_symbols, &self.roots] {
+			if let Err(e) = Patterns::new(patterns) {
+				return Err(Error::Config {
+					path: path.to_owned(),
+					message: e.to_string(),
+				});
+			}
+		}
+		Ok(())
+	}
+}
+
+/// The prefix of patterns that are regular expressions instead of globs
+const REGEX_PREFIX: &str = "regex:";
+
+/// A set of glob patterns, and of regular expressions prefixed with `regex:`
+#[derive(Clone, Debug)]
+pub struct Patterns {
+	globs: GlobSet,
+	regexes: RegexSet,
+}
+
+impl Default for Patterns {
+	fn default() -> Self {
+		Patterns {
+			globs: GlobSet::empty(),
+			regexes: RegexSet::empty(),
+		}
+	}
+}
+
+impl Patterns {
+	pub fn new(patterns: &[String]) -> Result<Self, Error> {
+		let mut globs = GlobSetBuilder::new();
+		let mut regexes = Vec::new();
+		for pattern in patterns {
+			if let Some(regex) = pattern.strip_prefix(REGEX_PREFIX) {
+				// Regexes have to match the entire string, like globs
+				regexes.push(format!("^(?:{regex})$"));
+				continue;
+			}
+			let glob = Glob::new(pattern)
+				.map_err(|e| Error::InvalidArgument(e.to_string()))?;
+			globs.add(glob);
+		}
+		let globs = globs.build()
+			.map_err(|e| Error::InvalidArgument(e.to_string()))?;
+		let regexes = RegexSet::new(regexes)
+			.map_err(|e| Error::InvalidArgument(e.to_string()))?;
+		Ok(Patterns {
+			globs,
+			regexes,
+		})
+	}
+	pub fn is_empty(&self) -> bool {
+		self.globs.is_empty() && self.regexes.is_empty()
+	}
+	pub fn is_match(&self, s: &str) -> bool {
+		self.globs.is_match(s) || self.regexes.is_match(s)
+	}
+	/// Whether the SCIP symbol, or the Rust path of the definition like `krate::module::Item`, matches
+	pub fn is_symbol_match(&self, sym: &str) -> bool {
+		if self.is_empty() {
+			return false;
+		}
+		self.is_match(sym) || crate::scip::item_path(sym)
+			.map(|path| self.is_match(&path))
+			.unwrap_or(false)
+	}
+}
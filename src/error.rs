@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
+
+/// The errors that can occur while analyzing a project
+#[derive(Debug)]
+pub enum Error {
+	Io(io::Error),
+	Json(serde_json::Error),
+	Protobuf(protobuf::Error),
+	/// A source file couldn't be parsed
+	Syn(syn::Error),
+	/// A SCIP symbol couldn't be parsed
+	Symbol { symbol: String, message: String },
+	/// A SCIP occurrence has a range with the wrong number of elements, or negative ones
+	Range(Vec<i32>),
+	/// A save-analysis id refers to a crate that isn't in the prelude
+	CrateId(u32),
+	/// The configuration file is invalid
+	Config { path: PathBuf, message: String },
+	/// An external command like `cargo metadata` failed
+	Command { command: String, message: String },
+	/// The input is not supported, like an index without a `file://` project root
+	Unsupported(String),
+	/// The arguments are invalid, like an unknown output format
+	InvalidArgument(String),
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Io(e) => write!(f, "{e}"),
+			Error::Json(e) => write!(f, "invalid JSON: {e}"),
+			Error::Protobuf(e) => write!(f, "invalid SCIP index: {e}"),
+			Error::Syn(e) => write!(f, "couldn't parse source: {e}"),
+			Error::Symbol { symbol, message } => write!(f, "invalid symbol '{symbol}': {message}"),
+			Error::Range(range) => write!(f, "invalid range: {range:?}"),
+			Error::CrateId(id) => write!(f, "unknown crate id {id}"),
+			Error::Config { path, message } => write!(f, "invalid configuration in {}: {message}", path.display()),
+			Error::Command { command, message } => write!(f, "{command} command failed: {message}"),
+			Error::Unsupported(msg) | Error::InvalidArgument(msg) => write!(f, "{msg}"),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io(e) => Some(e),
+			Error::Json(e) => Some(e),
+			Error::Protobuf(e) => Some(e),
+			Error::Syn(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Self {
+		Error::Io(e)
+	}
+}
+
+impl From<serde_json::Error> for Error {
+	fn from(e: serde_json::Error) -> Self {
+		Error::Json(e)
+	}
+}
+
+impl From<protobuf::Error> for Error {
+	fn from(e: protobuf::Error) -> Self {
+		Error::Protobuf(e)
+	}
+}
+
+impl From<syn::Error> for Error {
+	fn from(e: syn::Error) -> Self {
+		Error::Syn(e)
+	}
+}
+
+/// Counts of the entries of the input that couldn't be processed and were skipped
+#[derive(Default, Debug)]
+pub struct Skipped {
+	counts: BTreeMap<&'static str, usize>,
+}
+
+impl Skipped {
+	/// Records a skipped entry, described by what kind of entry it is
+	pub fn add(&mut self, what: &'static str) {
+		*self.counts.entry(what).or_default() += 1;
+	}
+	pub fn is_empty(&self) -> bool {
+		self.counts.is_empty()
+	}
+	/// Obtains a summary like `2 occurrences with invalid ranges, 1 malformed symbol`
+	pub fn summary(&self) -> String {
+		self.counts.iter()
+			.map(|(what, count)| format!("{count} {what}"))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+}
@@ -0,0 +1,257 @@
+use crate::{error::Skipped, source::Region, Error};
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, sync::Arc};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize)]
+pub struct Span {
+	pub file: Arc<str>,
+	pub start_line: u32,
+	pub start_col: u32,
+	pub end_line: u32,
+	pub end_col: u32,
+}
+
+impl Span {
+	/// Obtains file_name.rs:10:32 like format of the span
+	pub fn display_str(&self) -> String {
+		format!("{}:{}:{}", self.file, self.start_line, self.start_col)
+	}
+	pub fn region(&self) -> Region {
+		let (start, end) = (self.start(), self.end());
+		((start.0 as usize, start.1 as usize), (end.0 as usize, end.1 as usize))
+	}
+	pub(crate) fn start(&self) -> (u32, u32) {
+		(self.start_line, self.start_col)
+	}
+	pub(crate) fn end(&self) -> (u32, u32) {
+		(self.end_line, self.end_col)
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+	Public,
+	/// `pub(crate)`
+	Crate,
+	/// `pub(super)`, `pub(in path)`, `pub(self)`
+	Restricted,
+	Private,
+}
+
+impl Visibility {
+	/// Obtains the visibility from the start of an item's signature, like `pub fn foo()`
+	pub fn from_signature(sig: &str) -> Self {
+		if sig.starts_with("pub(crate)") {
+			Visibility::Crate
+		} else if sig.starts_with("pub(") {
+			Visibility::Restricted
+		} else if sig.starts_with("pub ") {
+			Visibility::Public
+		} else {
+			Visibility::Private
+		}
+	}
+}
+
+/// How severe a finding is
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+	/// Informational, doesn't count towards the thresholds
+	Note,
+	#[default]
+	Warning,
+	Error,
+}
+
+impl Severity {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Severity::Note => "note",
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		}
+	}
+}
+
+impl FromStr for Severity {
+	type Err = Error;
+	fn from_str(s: &str) -> Result<Self, Error> {
+		match s {
+			"note" => Ok(Severity::Note),
+			"warning" => Ok(Severity::Warning),
+			"error" => Ok(Severity::Error),
+			_ => Err(Error::InvalidArgument(format!("unknown severity '{s}', expected note, warning or error"))),
+		}
+	}
+}
+
+/// Why something has been reported
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+	/// The definition is not used anywhere
+	Unused,
+	/// The definition is only used by code that is unused itself
+	Unreachable,
+	/// An `#[expect(dead_code)]` attribute doesn't apply to anything unused
+	UnfulfilledExpectation,
+	/// The definition is `pub` but only used within its crate
+	CouldBeCrateVisible,
+	/// The definition is `pub(crate)` but only used within its module
+	CouldBePrivate,
+	/// The definition is only used by test code
+	TestOnly,
+	/// An import of a definition that isn't used anywhere
+	UnusedImport,
+	/// The field or static is written to, but never read
+	WriteOnly,
+	/// The enum variant is only used in patterns, but never constructed
+	NeverConstructed,
+	/// A `// warnalyzer: ignore` comment doesn't apply to any finding
+	StaleSuppression,
+}
+
+/// Something a backend reports, usually an unused definition
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Serialize)]
+pub struct Finding {
+	pub span: Span,
+	pub name: String,
+	pub kind: String,
+	/// The name of the crate the definition is in
+	#[serde(rename = "crate")]
+	pub krate: Option<String>,
+	pub visibility: Option<Visibility>,
+	pub reason: Reason,
+	pub severity: Severity,
+	/// A backend specific identifier of the definition, like its SCIP symbol
+	pub symbol: Option<String>,
+	/// For local variables, the identifier of the definition they are in
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub container: Option<String>,
+}
+
+/// Obtains a backend independent category from the kind of a definition
+fn kind_category(kind: &str) -> &'static str {
+	match kind {
+		"Function" => "function",
+		"Method" | "StaticMethod" | "TraitMethod" | "AbstractMethod" => "method",
+		"Struct" => "struct",
+		"Enum" => "enum",
+		"EnumMember" | "TupleVariant" | "StructVariant" => "variant",
+		"Union" => "union",
+		"Field" => "field",
+		"Trait" => "trait",
+		"Module" | "Mod" => "module",
+		"Constant" | "Const" => "constant",
+		"StaticVariable" | "Static" => "static",
+		"TypeAlias" | "Type" | "AssociatedType" => "type",
+		"Macro" => "macro",
+		"Variable" | "Parameter" | "Local" => "variable",
+		_ => "item",
+	}
+}
+
+/// The categories of kinds of definitions
+const KIND_CATEGORIES: &[&str] = &["function", "method", "struct", "enum", "variant", "union", "field",
+	"trait", "module", "constant", "static", "type", "macro", "variable", "item"];
+
+/// Whether the string is a category or rule id of findings, like `function` or `unused-function`
+pub fn is_known_category(s: &str) -> bool {
+	const OTHER: &[&str] = &["expectation", "visibility", "test-only", "import", "write-only", "never-constructed",
+		"suppression", "unfulfilled-expectation", "could-be-pub-crate", "could-be-private", "unused-import",
+		"stale-suppression"];
+	KIND_CATEGORIES.contains(&s) || OTHER.contains(&s) || ["unused-", "test-only-", "write-only-", "never-constructed-"].iter()
+		.filter_map(|prefix| s.strip_prefix(prefix))
+		.any(|kind| KIND_CATEGORIES.contains(&kind))
+}
+
+impl Finding {
+	/// Obtains the kind of definition independent of the backend, like `function`,
+	/// or `expectation` for unfulfilled expectations and `visibility` for too visible definitions
+	pub fn category(&self) -> &'static str {
+		match self.reason {
+			Reason::Unused | Reason::Unreachable => kind_category(&self.kind),
+			Reason::UnfulfilledExpectation => "expectation",
+			Reason::CouldBeCrateVisible | Reason::CouldBePrivate => "visibility",
+			Reason::TestOnly => "test-only",
+			Reason::UnusedImport => "import",
+			Reason::WriteOnly => "write-only",
+			Reason::NeverConstructed => "never-constructed",
+			Reason::StaleSuppression => "suppression",
+		}
+	}
+	/// Obtains an identifier of the category of the finding, like `unused-function`
+	pub fn rule_id(&self) -> String {
+		match self.reason {
+			Reason::Unused | Reason::Unreachable => format!("unused-{}", kind_category(&self.kind)),
+			Reason::UnfulfilledExpectation => "unfulfilled-expectation".to_owned(),
+			Reason::CouldBeCrateVisible => "could-be-pub-crate".to_owned(),
+			Reason::CouldBePrivate => "could-be-private".to_owned(),
+			Reason::TestOnly => format!("test-only-{}", kind_category(&self.kind)),
+			Reason::UnusedImport => "unused-import".to_owned(),
+			Reason::WriteOnly => format!("write-only-{}", kind_category(&self.kind)),
+			Reason::NeverConstructed => format!("never-constructed-{}", kind_category(&self.kind)),
+			Reason::StaleSuppression => "stale-suppression".to_owned(),
+		}
+	}
+	/// Obtains a description of the category of the finding
+	pub fn rule_description(&self) -> String {
+		match self.reason {
+			Reason::Unused | Reason::Unreachable => format!("Unused {}", kind_category(&self.kind)),
+			Reason::UnfulfilledExpectation => "Unfulfilled expect(dead_code) attribute".to_owned(),
+			Reason::CouldBeCrateVisible => "Public item only used within its crate".to_owned(),
+			Reason::CouldBePrivate => "pub(crate) item only used within its module".to_owned(),
+			Reason::TestOnly => format!("{} only used in tests", kind_category(&self.kind)),
+			Reason::UnusedImport => "Import of an unused item".to_owned(),
+			Reason::WriteOnly => format!("{} that is never read", kind_category(&self.kind)),
+			Reason::NeverConstructed => format!("{} that is never constructed", kind_category(&self.kind)),
+			Reason::StaleSuppression => "warnalyzer: ignore comment that doesn't suppress anything".to_owned(),
+		}
+	}
+	/// Obtains an identifier of the finding which doesn't change when code is moved around
+	pub fn stable_key(&self) -> String {
+		match (&self.symbol, &self.container) {
+			// The symbols of locals are only unique within the file, and change with it
+			(Some(_symbol), Some(container)) => format!("{}:{} {}", self.rule_id(),
+				crate::scip::symbol_without_version(container), self.name),
+			(Some(symbol), None) => format!("{}:{}", self.rule_id(), crate::scip::symbol_without_version(symbol)),
+			(None, _) => format!("{}:{}:{}:{}", self.rule_id(),
+				self.krate.as_deref().unwrap_or_default(), self.span.file, self.name),
+		}
+	}
+	/// Obtains the message describing the finding, without its location
+	pub fn message(&self) -> String {
+		match self.reason {
+			Reason::Unused => format!("unused {} '{}'", self.kind, self.name),
+			Reason::Unreachable => format!("unused {} '{}' (only used by unused code)", self.kind, self.name),
+			Reason::UnfulfilledExpectation => "unfulfilled expect(dead_code)".to_owned(),
+			Reason::CouldBeCrateVisible => format!("{} '{}' is pub but only used within its crate", self.kind, self.name),
+			Reason::CouldBePrivate => format!("{} '{}' is pub(crate) but only used within its module", self.kind, self.name),
+			Reason::TestOnly => format!("{} '{}' is only used in tests", self.kind, self.name),
+			Reason::UnusedImport => format!("unused import of {} '{}'", self.kind, self.name),
+			Reason::WriteOnly => format!("{} '{}' is written to, but never read", self.kind, self.name),
+			Reason::NeverConstructed => format!("{} '{}' is never constructed", self.kind, self.name),
+			Reason::StaleSuppression => "stale warnalyzer: ignore comment".to_owned(),
+		}
+	}
+	/// Obtains file_name.rs:10:32: message like format of the finding
+	///
+	/// The severity is only mentioned if it isn't the default one.
+	pub fn display_str(&self) -> String {
+		if self.severity == Severity::default() {
+			format!("{}: {}", self.span.display_str(), self.message())
+		} else {
+			format!("{}: {}: {}", self.span.display_str(), self.severity.as_str(), self.message())
+		}
+	}
+}
+
+/// A database of definitions and their uses that can find unused code
+pub trait Backend {
+	/// Obtains all findings, sorted by their location
+	fn get_findings(&self) -> Vec<Finding>;
+	/// Obtains the entries of the input that were skipped because they were malformed
+	fn skipped(&self) -> &Skipped;
+}
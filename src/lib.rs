@@ -1,21 +1,87 @@
 #[macro_use]
 extern crate log;
 
+pub mod baseline;
+pub mod config;
+pub mod error;
+pub mod finding;
+pub mod metadata;
+pub mod output;
 pub mod save_analysis;
 pub mod scip;
+pub mod source;
 
-use std::fmt::Display;
+pub use error::Error;
 
-#[derive(Debug)]
-pub struct StrErr(String);
+use config::Patterns;
+use finding::{Finding, Reason, Severity};
+use std::collections::BTreeMap;
 
-impl<T :Display> From<T> for StrErr {
-	fn from(v :T) -> Self {
-		StrErr(format!("{}", v))
-	}
-}
-
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Options {
 	pub recurse :bool,
+	/// Only report findings in these packages, or in all if empty
+	pub packages :Vec<String>,
+	/// Don't report findings in these packages
+	pub exclude :Vec<String>,
+	/// Packages whose public API is used from outside of the workspace
+	pub public_api :Vec<String>,
+	/// Report definitions whose visibility is larger than needed
+	pub check_visibility :bool,
+	/// Report definitions that are only used by test code
+	pub test_only :bool,
+	/// The severity of definitions only used by test code,
+	/// which takes precedence over the configured severities
+	pub test_only_severity :Option<Severity>,
+	/// Don't report findings in files matching these patterns
+	pub ignore_paths :Patterns,
+	/// Don't report findings for definitions whose symbol or path matches these patterns
+	pub ignore_symbols :Patterns,
+	/// Definitions whose symbol or path matches these patterns are used implicitly
+	pub roots :Patterns,
+	/// Severities by category or rule id of findings
+	pub severities :BTreeMap<String, Severity>,
+}
+
+/// Package names use `-` where crate names use `_`
+fn same_package(a :&str, b :&str) -> bool {
+	a.len() == b.len() && a.chars().zip(b.chars())
+		.all(|(a, b)| a == b || (a == '-' || a == '_') && (b == '-' || b == '_'))
+}
+
+impl Options {
+	/// Whether findings in the given package should be reported
+	///
+	/// Findings with an unknown package are only reported if no packages were selected.
+	pub fn reports_package(&self, name :Option<&str>) -> bool {
+		let Some(name) = name else {
+			return self.packages.is_empty();
+		};
+		let selected = self.packages.is_empty() ||
+			self.packages.iter().any(|p| same_package(p, name));
+		selected && !self.exclude.iter().any(|p| same_package(p, name))
+	}
+	/// Removes the findings that shouldn't be reported, and adjusts the severities of the rest
+	pub fn apply(&self, findings :&mut Vec<Finding>) {
+		findings.retain(|f| {
+			self.reports_package(f.krate.as_deref()) &&
+				!self.ignore_paths.is_match(&f.span.file) &&
+				!f.symbol.as_ref().map(|s| self.ignore_symbols.is_symbol_match(s)).unwrap_or(false)
+		});
+		for f in findings.iter_mut() {
+			// Rule ids are more specific than categories
+			let severity = self.severities.get(&f.rule_id())
+				.or_else(|| self.severities.get(f.category()));
+			if let Some(severity) = severity {
+				f.severity = *severity;
+			}
+			if let (Reason::TestOnly, Some(severity)) = (f.reason, self.test_only_severity) {
+				f.severity = severity;
+			}
+		}
+	}
+	/// Whether the public API of the given package is considered used
+	pub fn has_public_api(&self, name :&str) -> bool {
+		self.public_api.iter().any(|p| same_package(p, name))
+	}
 }
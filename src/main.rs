@@ -1,51 +1,451 @@
 #[macro_use]
 extern crate log;
 
-use std::fs::create_dir;
+use std::fs::create_dir_all;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-use warnalyzer::{scip::run_scip, Options, StrErr};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use warnalyzer::{baseline::Baseline, config::{Config, Patterns}, finding::{self, Backend, Finding, Severity}, metadata::Metadata, output::{write_findings, OutputFormat}, scip::{self, run_scip, Dump, DumpFilter}, Error, Options};
 
-fn main() -> Result<(), StrErr> {
+/// Show unused code from multi-crate Rust projects
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+	#[command(subcommand)]
+	command :Option<Command>,
+	#[command(flatten)]
+	check :CheckArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Report unused code (the default)
+	Check(CheckArgs),
+	/// Generate the SCIP index of a project via rust-analyzer
+	Index(IndexArgs),
+	/// Print the contents of a SCIP index
+	Dump(DumpArgs),
+	/// Explain why a definition is reported or not
+	Explain(ExplainArgs),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum BackendKind {
+	Scip,
+	SaveAnalysis,
+}
+
+#[derive(Args)]
+struct IndexOptions {
+	/// Path of the SCIP index to write when analyzing a project directory
+	/// [default: index.scip in the target directory]
+	#[arg(long)]
+	index :Option<PathBuf>,
+	/// Additional argument to pass to `rust-analyzer scip`, can be repeated
+	#[arg(long = "rust-analyzer-arg", value_name = "ARG", allow_hyphen_values = true)]
+	rust_analyzer_args :Vec<String>,
+}
+
+#[derive(Args)]
+struct InputArgs {
+	/// Project directories or SCIP indexes, which are analyzed together,
+	/// or the save-analysis JSON file of the leaf crate
+	#[arg(value_name = "PATH")]
+	paths :Vec<PathBuf>,
+	/// The backend to use [default: guessed from the path]
+	#[arg(long, value_enum)]
+	backend :Option<BackendKind>,
+	/// Also report code that is only used by unused code
+	#[arg(long)]
+	recurse :bool,
+	/// Only report findings in this workspace member, can be repeated
+	#[arg(short, long = "package", value_name = "NAME")]
+	packages :Vec<String>,
+	/// Don't report findings in this workspace member, can be repeated
+	#[arg(long, value_name = "NAME")]
+	exclude :Vec<String>,
+	/// Consider the public API of this workspace member used, can be repeated
+	#[arg(long, value_name = "NAME")]
+	public_api :Vec<String>,
+	/// Also report `pub` items only used within their crate,
+	/// and `pub(crate)` items only used within their module
+	#[arg(long)]
+	check_visibility :bool,
+	/// Also report definitions that are only used by test code
+	#[arg(long)]
+	test_only :bool,
+	/// Severity of definitions only used by test code: note, warning or error
+	/// [default: the configured one, or warning]
+	#[arg(long, value_parser = parse_severity)]
+	test_only_severity :Option<Severity>,
+	/// Consider definitions matching the pattern used, can be repeated.
+	/// Matches SCIP symbols or paths like `krate::module::item`, as glob or as `regex:...`
+	#[arg(long = "root", value_name = "PATTERN")]
+	roots :Vec<String>,
+	/// Configuration file [default: warnalyzer.toml or [workspace.metadata.warnalyzer]
+	/// of the workspace of the first path]
+	#[arg(long, value_name = "FILE")]
+	config :Option<PathBuf>,
+	#[command(flatten)]
+	index :IndexOptions,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+	#[command(flatten)]
+	input :InputArgs,
+	/// Output format: text, json, jsonl or sarif [default: text]
+	#[arg(long, value_parser = parse_format)]
+	format :Option<OutputFormat>,
+	/// Only report findings not contained in this baseline file
+	#[arg(long, value_name = "FILE")]
+	baseline :Option<PathBuf>,
+	/// Record the current findings in this baseline file
+	#[arg(long, value_name = "FILE")]
+	write_baseline :Option<PathBuf>,
+	#[command(flatten)]
+	thresholds :Thresholds,
+}
+
+/// Limits on the number of findings, exceeding them makes the check fail
+#[derive(Args)]
+struct Thresholds {
+	/// Fail if any findings are reported, same as `--max-findings 0`
+	#[arg(long)]
+	deny :bool,
+	/// Fail if more than N findings are reported
+	#[arg(long, value_name = "N")]
+	max_findings :Option<usize>,
+	/// Fail if more than N findings of the category or rule id are reported,
+	/// like `function=0` or `unused-function=0`, can be repeated
+	#[arg(long, value_name = "KIND=N", value_parser = parse_kind_limit)]
+	max_findings_of :Vec<(String, usize)>,
+}
+
+impl Thresholds {
+	/// Obtains descriptions of the limits the findings exceed
+	fn exceeded(&self, findings :&[Finding]) -> Vec<String> {
+		let findings = findings.iter()
+			.filter(|f| f.severity != Severity::Note)
+			.collect::<Vec<_>>();
+		let mut exceeded = Vec::new();
+		let max = if self.deny { Some(0) } else { self.max_findings };
+		if let Some(max) = max {
+			if findings.len() > max {
+				exceeded.push(format!("{} findings, at most {} allowed", findings.len(), max));
+			}
+		}
+		for (kind, max) in &self.max_findings_of {
+			let count = findings.iter()
+				.filter(|f| f.category() == kind || f.rule_id() == *kind)
+				.count();
+			if count > *max {
+				exceeded.push(format!("{} {} findings, at most {} allowed", count, kind, max));
+			}
+		}
+		exceeded
+	}
+}
+
+/// The check ran and didn't exceed any thresholds
+const EXIT_OK :u8 = 0;
+/// The check ran but the findings exceeded a threshold
+const EXIT_FINDINGS :u8 = 1;
+/// The analysis itself failed
+const EXIT_ERROR :u8 = 2;
+
+#[derive(Args)]
+struct IndexArgs {
+	/// Project directory, or any directory of the workspace
+	path :PathBuf,
+	#[command(flatten)]
+	index :IndexOptions,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpFormat {
+	Text,
+	Json,
+}
+
+#[derive(Args)]
+struct DumpArgs {
+	/// Project directories or SCIP indexes, which are merged
+	#[arg(value_name = "PATH", required = true)]
+	paths :Vec<PathBuf>,
+	/// The backend to use, only scip is supported [default: guessed from the path]
+	#[arg(long, value_enum)]
+	backend :Option<BackendKind>,
+	#[command(flatten)]
+	index :IndexOptions,
+	/// Only print documents whose path matches the glob (or `regex:...`), can be repeated
+	#[arg(long, value_name = "PATTERN")]
+	document :Vec<String>,
+	/// Only print symbols starting with the prefix, as SCIP symbol or as path like `krate::module`,
+	/// can be repeated
+	#[arg(long, value_name = "PREFIX")]
+	symbol :Vec<String>,
+	/// Only print symbols of the kind, like `Function` or `Struct`, can be repeated
+	#[arg(long)]
+	kind :Vec<String>,
+	/// Output format
+	#[arg(long, value_enum, default_value = "text")]
+	format :DumpFormat,
+}
+
+#[derive(Args)]
+struct ExplainArgs {
+	/// The SCIP symbol or `path:line:col` location of the definition
+	#[arg(value_name = "ITEM")]
+	item :String,
+	// Analyzes the current directory if no path is given
+	#[command(flatten)]
+	input :InputArgs,
+}
+
+fn parse_format(s :&str) -> Result<OutputFormat, String> {
+	s.parse().map_err(|e :Error| e.to_string())
+}
+
+fn parse_severity(s :&str) -> Result<Severity, String> {
+	s.parse().map_err(|e :Error| e.to_string())
+}
+
+fn parse_kind_limit(s :&str) -> Result<(String, usize), String> {
+	let (kind, max) = s.split_once('=')
+		.ok_or_else(|| format!("expected KIND=N, found '{}'", s))?;
+	if !finding::is_known_category(kind) {
+		return Err(format!("unknown category or rule id '{}'", kind));
+	}
+	let max = max.parse().map_err(|e| format!("invalid limit '{}': {}", max, e))?;
+	Ok((kind.to_owned(), max))
+}
+
+fn main() -> ExitCode {
 	pretty_env_logger::init();
-	let path = std::env::args().nth(1).expect("please specify path");
-	info!("{}", path);
-	let options = Options {
-		recurse : false,
+	let cli = Cli::parse();
+	match run(cli) {
+		Ok(code) => ExitCode::from(code),
+		Err(e) => {
+			eprintln!("error: {}", e);
+			ExitCode::from(EXIT_ERROR)
+		},
+	}
+}
+
+fn run(cli :Cli) -> Result<u8, Error> {
+	match cli.command {
+		None => check(&cli.check),
+		Some(Command::Check(args)) => check(&args),
+		Some(Command::Index(args)) => {
+			let index_path = generate_index(&args.path, &args.index)?;
+			println!("{}", index_path.display());
+			Ok(EXIT_OK)
+		},
+		Some(Command::Dump(args)) => {
+			let index_paths = scip_index_paths(&args.paths, args.backend, &args.index)?;
+			let indexes = index_paths.iter()
+				.map(|path| scip::parse_scip_index(path))
+				.collect::<Result<Vec<_>, _>>()?;
+			let index = scip::merge_indexes(indexes)?;
+			let filter = DumpFilter {
+				documents : Patterns::new(&args.document)?,
+				symbol_prefixes : args.symbol,
+				kinds : args.kind,
+			};
+			let dump = Dump::new(&index, &filter);
+			let mut w = std::io::stdout().lock();
+			match args.format {
+				DumpFormat::Text => dump.write_text(&mut w)?,
+				DumpFormat::Json => {
+					serde_json::to_writer_pretty(&mut w, &dump)?;
+					writeln!(w)?;
+				},
+			}
+			Ok(EXIT_OK)
+		},
+		Some(Command::Explain(mut args)) => {
+			if args.input.paths.is_empty() {
+				args.input.paths.push(PathBuf::from("."));
+			}
+			let config = load_config(&args.input)?;
+			let db = open_scip(&args.input, &config)?;
+			let Some(sym) = db.find_definition(&args.item) else {
+				return Err(Error::InvalidArgument(format!("no definition found for '{}'", args.item)));
+			};
+			db.explain(&mut std::io::stdout().lock(), sym)?;
+			Ok(EXIT_OK)
+		},
+	}
+}
+
+fn generate_index(project :&Path, options :&IndexOptions) -> Result<PathBuf, Error> {
+	// Index the entire workspace, even if the path points to one of its members
+	let metadata = Metadata::for_dir(project)?;
+	let index_path = match &options.index {
+		Some(path) => path.clone(),
+		None => metadata.index_path(),
 	};
-	let is_json = path.ends_with(".json");
-	let is_scip = path.ends_with(".scip");
-	if is_json {
-		let db = warnalyzer::save_analysis::db::AnalysisDb::from_path(&path, options)?;
-		for ud in db.get_unused_defs() {
-			println!("{}: unused {} '{}'", ud.span.display_str(), ud.kind, ud.name);
+	if let Some(dir) = index_path.parent() {
+		create_dir_all(dir)?;
+	}
+	run_scip(&metadata.workspace_root, &index_path, &options.rust_analyzer_args)?;
+	Ok(index_path)
+}
+
+fn guess_backend_kind(path :&Path) -> Result<BackendKind, Error> {
+	match path.extension().and_then(|ext| ext.to_str()) {
+		Some("json") => Ok(BackendKind::SaveAnalysis),
+		Some("scip") => Ok(BackendKind::Scip),
+		_ if path.is_dir() => Ok(BackendKind::Scip),
+		_ => Err(Error::InvalidArgument(format!("Path '{}' doesn't exist or has unknown extension", path.display()))),
+	}
+}
+
+fn backend_kind(backend :Option<BackendKind>, paths :&[PathBuf]) -> Result<BackendKind, Error> {
+	if let Some(backend) = backend {
+		return Ok(backend);
+	}
+	let kinds = paths.iter()
+		.map(|path| guess_backend_kind(path))
+		.collect::<Result<Vec<_>, _>>()?;
+	if kinds.windows(2).any(|w| w[0] != w[1]) {
+		return Err(Error::InvalidArgument("paths for different backends can't be mixed".to_owned()));
+	}
+	Ok(kinds[0])
+}
+
+fn input_paths(args :&InputArgs) -> Result<&[PathBuf], Error> {
+	if args.paths.is_empty() {
+		return Err(Error::InvalidArgument("please specify path".to_owned()));
+	}
+	Ok(&args.paths)
+}
+
+/// Reads the configuration given on the command line, or the one of the first path's workspace
+fn load_config(args :&InputArgs) -> Result<Config, Error> {
+	if let Some(path) = &args.config {
+		return Config::from_file(path);
+	}
+	let paths = input_paths(args)?;
+	let path = paths[0].as_path();
+	// Indexes can be outside of the workspace, like in a `CARGO_TARGET_DIR`
+	let index_root = if !path.is_dir() && backend_kind(args.backend, paths)? == BackendKind::Scip {
+		match scip::index_project_root(path) {
+			Ok(root) => root,
+			Err(e) => {
+				info!("Couldn't read the project root of {}: {}", path.display(), e);
+				None
+			},
 		}
-	} else if is_scip {
-		report_scip(&path, options)?;
 	} else {
-		let path = std::path::Path::new(&path);
-		if path.is_dir() {
-			let target_dir = path.join("target");
-			if !target_dir.exists() {
-				create_dir(&target_dir)?;
-			}
-			let index_path = target_dir.join("index.scip");
-			run_scip(&path, &index_path)?;
-			report_scip(index_path.to_str().unwrap(), options)?;
-		} else {
-			eprintln!("Path '{}' doesn't exist or has unknown extension", path.display());
+		None
+	};
+	let dir = if path.is_dir() {
+		path
+	} else if let Some(root) = &index_root {
+		root
+	} else {
+		match path.parent() {
+			Some(dir) if !dir.as_os_str().is_empty() => dir,
+			_ => Path::new("."),
 		}
+	};
+	match Metadata::for_dir(dir) {
+		Ok(metadata) => Config::for_workspace(&metadata),
+		Err(e) => {
+			info!("Not reading configuration, couldn't obtain cargo metadata for {}: {}", dir.display(), e);
+			Ok(Config::default())
+		},
+	}
+}
+
+fn options(args :&InputArgs, config :&Config) -> Result<Options, Error> {
+	Ok(Options {
+		recurse : args.recurse,
+		// The packages given on the command line replace the configured ones
+		packages : if args.packages.is_empty() { config.packages.clone() } else { args.packages.clone() },
+		exclude : args.exclude.iter().chain(&config.exclude).cloned().collect(),
+		public_api : args.public_api.iter().chain(&config.public_api).cloned().collect(),
+		check_visibility : args.check_visibility,
+		test_only : args.test_only,
+		test_only_severity : args.test_only_severity,
+		ignore_paths : Patterns::new(&config.ignore_paths)?,
+		ignore_symbols : Patterns::new(&config.ignore_symbols)?,
+		roots : Patterns::new(&args.roots.iter().chain(&config.roots).cloned().collect::<Vec<_>>())?,
+		severities : config.severity.clone(),
+	})
+}
+
+/// Obtains the paths of the SCIP indexes, generating them for the project directories among the paths
+fn scip_index_paths(paths :&[PathBuf], backend :Option<BackendKind>, options :&IndexOptions) -> Result<Vec<PathBuf>, Error> {
+	if backend_kind(backend, paths)? != BackendKind::Scip {
+		return Err(Error::Unsupported("this command is only supported by the scip backend".to_owned()));
+	}
+	if options.index.is_some() && paths.iter().filter(|path| path.is_dir()).count() > 1 {
+		return Err(Error::InvalidArgument("--index can only be used with a single project directory".to_owned()));
 	}
+	paths.iter()
+		.map(|path| if path.is_dir() {
+			generate_index(path, options)
+		} else {
+			Ok(path.to_owned())
+		})
+		.collect()
+}
 
-	Ok(())
+fn open_scip(args :&InputArgs, config :&Config) -> Result<warnalyzer::scip::AnalysisDb, Error> {
+	let index_paths = scip_index_paths(input_paths(args)?, args.backend, &args.index)?;
+	let index_paths = index_paths.iter()
+		.map(|path| path.as_path())
+		.collect::<Vec<_>>();
+	warnalyzer::scip::AnalysisDb::from_paths(&index_paths, options(args, config)?)
+}
+
+fn check(args :&CheckArgs) -> Result<u8, Error> {
+	let paths = input_paths(&args.input)?;
+	info!("{:?}", paths);
+	let config = load_config(&args.input)?;
+	match backend_kind(args.input.backend, paths)? {
+		BackendKind::SaveAnalysis => {
+			let [path] = paths else {
+				return Err(Error::Unsupported("the save-analysis backend only supports a single path".to_owned()));
+			};
+			let db = warnalyzer::save_analysis::db::AnalysisDb::from_path(path, options(&args.input, &config)?)?;
+			report(&db, args, &config)
+		},
+		BackendKind::Scip => {
+			let db = open_scip(&args.input, &config)?;
+			report(&db, args, &config)
+		},
+	}
 }
 
-fn report_scip(path: &str, options: Options) -> Result<(), StrErr> {
-	let db = warnalyzer::scip::AnalysisDb::from_path(&path, options)?;
-	//db.dump_index()?;
-	for ud in db.get_unused_defs() {
-		let kind = ud.kind.map(|s| format!("{s:?}")).unwrap_or_else(|| "<unknown>".to_owned());
-		let name = ud.name.unwrap_or_default();
-		println!("{}: unused {} '{}'", ud.span.display_str(), kind, name);
+fn report(backend :&dyn Backend, args :&CheckArgs, config :&Config) -> Result<u8, Error> {
+	let mut findings = backend.get_findings();
+	let skipped = backend.skipped();
+	if !skipped.is_empty() {
+		eprintln!("warning: skipped malformed entries of the input: {}", skipped.summary());
 	}
-	Ok(())
-}
\ No newline at end of file
+	if let Some(path) = &args.write_baseline {
+		Baseline::from_findings(&findings).save(path)?;
+		info!("wrote baseline with {} entries to {}", findings.len(), path.display());
+	}
+	if let Some(path) = args.baseline.as_ref().or(config.output.baseline.as_ref()) {
+		let baseline = Baseline::load(path)?;
+		let (new, gone) = baseline.apply(findings);
+		for entry in gone {
+			eprintln!("{}: no longer found: {}", entry.location, entry.message);
+		}
+		findings = new;
+	}
+	let format = args.format.or(config.output.format).unwrap_or_default();
+	write_findings(&mut std::io::stdout().lock(), format, &findings)?;
+	let exceeded = args.thresholds.exceeded(&findings);
+	for msg in &exceeded {
+		eprintln!("threshold exceeded: {}", msg);
+	}
+	Ok(if exceeded.is_empty() { EXIT_OK } else { EXIT_FINDINGS })
+}
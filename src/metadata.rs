@@ -0,0 +1,77 @@
+use serde::Deserialize;
+
+use crate::Error;
+use std::{path::{Path, PathBuf}, process::Command};
+
+/// The subset of the output of `cargo metadata` that we are interested in
+#[derive(Deserialize, Debug)]
+pub struct Metadata {
+	pub packages: Vec<Package>,
+	/// Ids of the packages that are members of the workspace
+	pub workspace_members: Vec<String>,
+	pub workspace_root: PathBuf,
+	pub target_directory: PathBuf,
+	/// The `[workspace.metadata]` table of the workspace's manifest
+	#[serde(rename = "metadata")]
+	pub workspace_metadata: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Package {
+	pub id: String,
+	pub name: String,
+	pub manifest_path: PathBuf,
+	pub targets: Vec<Target>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Target {
+	pub name: String,
+	pub kind: Vec<String>,
+	pub src_path: PathBuf,
+}
+
+impl Target {
+	/// Whether the target is compiled to an executable with a `main` function
+	pub fn has_main(&self) -> bool {
+		self.kind.iter()
+			.any(|k| matches!(k.as_str(), "bin" | "example" | "test" | "bench" | "custom-build"))
+	}
+}
+
+impl Metadata {
+	/// Invokes `cargo metadata` for the workspace containing `dir`
+	pub fn for_dir(dir: &Path) -> Result<Self, Error> {
+		let output = Command::new("cargo")
+			.arg("metadata")
+			.arg("--format-version")
+			.arg("1")
+			.arg("--no-deps")
+			.current_dir(dir)
+			.output()?;
+		if !output.status.success() {
+			let stderr = String::from_utf8_lossy(&output.stderr);
+			return Err(Error::Command {
+				command: "cargo metadata".to_owned(),
+				message: stderr.trim().to_owned(),
+			});
+		}
+		let metadata = serde_json::from_slice(&output.stdout)?;
+		Ok(metadata)
+	}
+	/// The packages that are members of the workspace
+	pub fn members(&self) -> impl Iterator<Item=&Package> {
+		self.packages.iter()
+			.filter(|p| self.workspace_members.contains(&p.id))
+	}
+	/// The targets of the workspace members
+	pub fn targets(&self) -> impl Iterator<Item=&Target> {
+		self.members()
+			.flat_map(|p| p.targets.iter())
+	}
+	/// The default location of the SCIP index of the workspace,
+	/// inside the target directory
+	pub fn index_path(&self) -> PathBuf {
+		self.target_directory.join("index.scip")
+	}
+}
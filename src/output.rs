@@ -0,0 +1,127 @@
+use crate::{finding::Finding, Error};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, io::Write, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+	/// `path:line:col: message` lines
+	#[default]
+	Text,
+	/// A JSON array of all findings
+	Json,
+	/// One JSON object per finding and line
+	#[serde(rename = "jsonl", alias = "json-lines")]
+	JsonLines,
+	/// SARIF 2.1.0, as consumed by code scanning tools
+	Sarif,
+}
+
+impl FromStr for OutputFormat {
+	type Err = Error;
+	fn from_str(s: &str) -> Result<Self, Error> {
+		match s {
+			"text" => Ok(OutputFormat::Text),
+			"json" => Ok(OutputFormat::Json),
+			"jsonl" | "json-lines" => Ok(OutputFormat::JsonLines),
+			"sarif" => Ok(OutputFormat::Sarif),
+			_ => Err(Error::InvalidArgument(format!("unknown output format '{s}'"))),
+		}
+	}
+}
+
+pub fn write_findings(w: &mut impl Write, format: OutputFormat, findings: &[Finding]) -> Result<(), Error> {
+	match format {
+		OutputFormat::Text => {
+			for finding in findings {
+				writeln!(w, "{}", finding.display_str())?;
+			}
+		},
+		OutputFormat::Json => {
+			serde_json::to_writer_pretty(&mut *w, findings)?;
+			writeln!(w)?;
+		},
+		OutputFormat::JsonLines => {
+			for finding in findings {
+				serde_json::to_writer(&mut *w, finding)?;
+				writeln!(w)?;
+			}
+		},
+		OutputFormat::Sarif => {
+			serde_json::to_writer_pretty(&mut *w, &sarif_log(findings))?;
+			writeln!(w)?;
+		},
+	}
+	Ok(())
+}
+
+/// FNV-1a, which unlike the hasher of std is guaranteed to be stable
+fn fnv1a(s: &str) -> u64 {
+	s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+		(hash ^ b as u64).wrapping_mul(0x100000001b3)
+	})
+}
+
+fn sarif_log(findings: &[Finding]) -> Value {
+	let mut rule_ids = BTreeMap::new();
+	for finding in findings {
+		rule_ids.entry(finding.rule_id())
+			.or_insert_with(|| (finding.rule_description(), finding.severity));
+	}
+	let rule_indices = rule_ids.keys()
+		.enumerate()
+		.map(|(i, id)| (id.clone(), i))
+		.collect::<BTreeMap<_, _>>();
+	let rules = rule_ids.iter()
+		.map(|(id, (description, severity))| json!({
+			"id": id,
+			"shortDescription": { "text": description },
+			"defaultConfiguration": { "level": severity.as_str() },
+		}))
+		.collect::<Vec<_>>();
+	let results = findings.iter()
+		.map(|finding| {
+			let rule_id = finding.rule_id();
+			let span = &finding.span;
+			json!({
+				"ruleId": rule_id,
+				"ruleIndex": rule_indices[&rule_id],
+				"level": finding.severity.as_str(),
+				"message": { "text": finding.message() },
+				"locations": [{
+					"physicalLocation": {
+						"artifactLocation": {
+							"uri": &*span.file,
+							"uriBaseId": "%SRCROOT%",
+						},
+						"region": {
+							"startLine": span.start_line,
+							"startColumn": span.start_col,
+							"endLine": span.end_line,
+							"endColumn": span.end_col,
+						},
+					},
+				}],
+				"partialFingerprints": {
+					"warnalyzerKey/v1": format!("{:016x}", fnv1a(&finding.stable_key())),
+				},
+			})
+		})
+		.collect::<Vec<_>>();
+	json!({
+		"$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+		"version": "2.1.0",
+		"runs": [{
+			"tool": {
+				"driver": {
+					"name": "warnalyzer",
+					"informationUri": "https://github.com/est31/warnalyzer",
+					"version": env!("CARGO_PKG_VERSION"),
+					"rules": rules,
+				},
+			},
+			"results": results,
+		}],
+	})
+}
@@ -1,13 +1,17 @@
 use super::defs::{CrateSaveAnalysis, CrateDisambiguator,
 	CrateSaveAnalysisMetadata};
-use crate::{StrErr, Options};
+use crate::{Error, Options};
+use crate::error::Skipped;
+use crate::finding::{Backend, Finding, Reason, Severity};
+use crate::metadata::Metadata;
+use crate::source::{self, SourceCache};
 use std::path::{Path, PathBuf};
 use std::collections::{HashSet, HashMap};
 use rayon::prelude::*;
 
 use super::mute::MuteSpansCache;
 
-use super::defs::{Def, Ref, ItemId, Prelude};
+use super::defs::{Def, Ref, ItemId, Prelude, Span};
 
 pub type AbsItemId = ItemId<CrateDisambiguator>;
 
@@ -18,71 +22,78 @@ pub struct AnalysisDb {
 	options :Options,
 	root :Option<PathBuf>,
 	covered_crates :HashSet<CrateDisambiguator>,
+	crate_names :HashMap<CrateDisambiguator, String>,
 	defs :HashMap<AbsItemId, AbsDef>,
 	refs :HashMap<AbsItemId, AbsRef>,
+	sources :SourceCache,
+	skipped :Skipped,
 }
 
 impl<T> ItemId<T> {
-	fn clone_map<U>(&self, f :impl FnOnce(&T) -> U) -> ItemId<U> {
-		ItemId {
-			krate : f(&self.krate),
+	fn try_clone_map<U, E>(&self, f :impl FnOnce(&T) -> Result<U, E>) -> Result<ItemId<U>, E> {
+		Ok(ItemId {
+			krate : f(&self.krate)?,
 			index : self.index,
-		}
+		})
 	}
 }
 
 impl<T> Def<T> {
-	fn clone_map<U>(&self, f :impl Fn(&T) -> U) -> Def<U> {
-		Def {
+	fn try_clone_map<U, E>(&self, f :impl Fn(&T) -> Result<U, E>) -> Result<Def<U>, E> {
+		Ok(Def {
 			kind : self.kind.clone(),
 			name : self.name.clone(),
-			id : self.id.clone_map(&f),
+			id : self.id.try_clone_map(&f)?,
 			span : self.span.clone(),
-			parent : self.parent.as_ref().map(|v| v.clone_map(&f)),
-			decl_id : self.decl_id.as_ref().map(|v| v.clone_map(&f)),
-		}
+			parent : self.parent.as_ref().map(|v| v.try_clone_map(&f)).transpose()?,
+			decl_id : self.decl_id.as_ref().map(|v| v.try_clone_map(&f)).transpose()?,
+			value : self.value.clone(),
+		})
 	}
 }
 
 impl<T> Ref<T> {
-	fn clone_map<U>(&self, f :impl FnOnce(&T) -> U) -> Ref<U> {
-		Ref {
+	fn try_clone_map<U, E>(&self, f :impl FnOnce(&T) -> Result<U, E>) -> Result<Ref<U>, E> {
+		Ok(Ref {
 			kind : self.kind.clone(),
-			ref_id : ItemId {
-				krate : f(&self.ref_id.krate),
-				index : self.ref_id.index,
-			},
+			ref_id : self.ref_id.try_clone_map(f)?,
 			span : self.span.clone(),
-		}
+		})
 	}
 }
 
-fn parse_save_analysis(path :&Path) -> Result<CrateSaveAnalysis, StrErr> {
+fn parse_save_analysis(path :&Path) -> Result<CrateSaveAnalysis, Error> {
 	let file = std::fs::read_to_string(path)?;
 	let file_parsed :CrateSaveAnalysis = serde_json::from_str(&file)?;
 	Ok(file_parsed)
 }
-fn parse_analysis_metadata(path :&Path) -> Result<CrateSaveAnalysisMetadata, StrErr> {
+fn parse_analysis_metadata(path :&Path) -> Result<CrateSaveAnalysisMetadata, Error> {
 	let file = std::fs::read_to_string(path)?;
 	let file_parsed :CrateSaveAnalysisMetadata = serde_json::from_str(&file)?;
 	Ok(file_parsed)
 }
 
 impl Prelude {
-	fn disambiguator_for_id(&self, id :u32) -> CrateDisambiguator {
+	fn disambiguator_for_id(&self, id :u32) -> Result<CrateDisambiguator, Error> {
 		if id == 0 {
-			return self.crate_id.disambiguator;
+			return Ok(self.crate_id.disambiguator);
+		}
+		match self.external_crates.get((id - 1) as usize) {
+			Some(krate) if krate.num == id => Ok(krate.id.disambiguator),
+			_ => Err(Error::CrateId(id)),
 		}
-		let krate = &self.external_crates[(id - 1) as usize];
-		assert_eq!(krate.num, id);
-		krate.id.disambiguator
 	}
 }
 
 impl AnalysisDb {
-	pub fn from_path(path :&str, options :Options) -> Result<Self, StrErr> {
-		let path = Path::new(path);
-		let leaf_parsed = parse_analysis_metadata(&path)?;
+	pub fn from_path(path :&Path, options :Options) -> Result<Self, Error> {
+		if !options.public_api.is_empty() {
+			warn!("Treating the public API as used is not supported by the save-analysis backend");
+		}
+		if !options.roots.is_empty() || !options.ignore_symbols.is_empty() {
+			warn!("Symbol patterns are not supported by the save-analysis backend");
+		}
+		let leaf_parsed = parse_analysis_metadata(path)?;
 		let mut disambiguators = leaf_parsed.prelude.external_crates.iter()
 			.map(|v| v.id.disambiguator)
 			.collect::<HashSet<_>>();
@@ -92,7 +103,7 @@ impl AnalysisDb {
 		let mut covered_crates = HashSet::new();
 		let v :Vec<_> = std::fs::read_dir(dir_path)?
 			.collect::<Vec<_>>()
-			.into_par_iter().map(|entry| -> Result<_, StrErr> {
+			.into_par_iter().map(|entry| -> Result<_, Error> {
 				let entry = entry?;
 				let path = entry.path();
 				let metadata = parse_analysis_metadata(&path)?;
@@ -106,10 +117,10 @@ impl AnalysisDb {
 				// Just focus on path deps for now.
 				if metadata.compilation.directory.contains(".cargo/registry/src/github.com") ||
 						metadata.compilation.directory.contains(".cargo/git/") {
-					info!("i> {}", path.to_str().unwrap());
+					info!("i> {}", path.display());
 					return Ok(None);
 				}
-				info!("p> {}", path.to_str().unwrap());
+				info!("p> {}", path.display());
 				let file_parsed = parse_save_analysis(&path)?;
 				Ok(Some((disambiguator, file_parsed)))
 		}).collect();
@@ -120,46 +131,94 @@ impl AnalysisDb {
 				crates.insert(disambiguator, file_parsed);
 			}
 		}
+		let mut crate_names = HashMap::new();
+		for (_dis, c) in crates.iter() {
+			let crate_ids = c.prelude.external_crates.iter()
+				.map(|ext| &ext.id)
+				.chain(std::iter::once(&c.prelude.crate_id));
+			for id in crate_ids {
+				crate_names.insert(id.disambiguator, id.name.clone());
+			}
+		}
+		let mut skipped = Skipped::default();
 		let mut defs = HashMap::new();
 		for (_dis, c) in crates.iter() {
 			for v in c.defs.iter() {
-				let v = v.clone_map(|w| c.prelude.disambiguator_for_id(*w));
-				defs.insert(v.id, v);
+				match v.try_clone_map(|w| c.prelude.disambiguator_for_id(*w)) {
+					Ok(v) => {
+						defs.insert(v.id, v);
+					},
+					Err(e) => {
+						warn!("Skipping definition {} in {}: {}", v.name, v.span.file_name, e);
+						skipped.add("definitions with unknown crates");
+					},
+				}
 			}
 		}
 		let mut refs = HashMap::new();
 		for (_dis, c) in crates.iter() {
 			for v in c.refs.iter() {
-				let v = v.clone_map(|w| c.prelude.disambiguator_for_id(*w));
-				refs.insert(v.ref_id, v);
+				match v.try_clone_map(|w| c.prelude.disambiguator_for_id(*w)) {
+					Ok(v) => {
+						refs.insert(v.ref_id, v);
+					},
+					Err(e) => {
+						warn!("Skipping reference in {}: {}", v.span.file_name, e);
+						skipped.add("references with unknown crates");
+					},
+				}
 			}
 		}
 		//println!("{:#?}", defs);
 		//println!("{:#?}", refs);
 
-		let root = path.parent()
-			.and_then(|p| p.parent())
-			.and_then(|p| p.parent())
-			.and_then(|p| p.parent())
-			.and_then(|p| p.parent())
-			.map(|p| p.to_owned());
+		// The file names are relative to the directory cargo invoked rustc in,
+		// which is the workspace root.
+		let compile_dir = Path::new(&leaf_parsed.compilation.directory);
+		let root = match Metadata::for_dir(compile_dir) {
+			Ok(metadata) => Some(metadata.workspace_root),
+			Err(e) => {
+				warn!("Couldn't obtain cargo metadata for {}: {}", compile_dir.display(), e);
+				Some(compile_dir.to_owned())
+			},
+		};
+		let mut sources = SourceCache::new(root.as_deref().unwrap_or(Path::new("")));
+		// Module definitions are at the `mod name;` declaration, their value is the module's file
+		for d in defs.values().filter(|d| d.kind == "Mod" && !d.value.is_empty()) {
+			let file = Path::new(&d.value);
+			let file = root.as_deref()
+				.and_then(|root| file.strip_prefix(root).ok())
+				.unwrap_or(file);
+			let pos = (d.span.line_start as usize, d.span.column_start as usize);
+			sources.add_mod_decl(&file.to_string_lossy(), &d.span.file_name, pos);
+		}
 		Ok(AnalysisDb {
 			options,
 			root,
 			covered_crates,
+			crate_names,
 			defs,
 			refs,
+			sources,
+			skipped,
 		})
 	}
-	pub fn get_unused_defs(&self) -> impl Iterator<Item=&AbsDef> {
+	/// Obtains the unused definitions, including those where it's allowed by an attribute
+	fn unsuppressed_unused_defs(&self) -> Vec<&AbsDef> {
 		let mut used_defs = HashSet::new();
 		for (_rid, r) in self.refs.iter() {
 			used_defs.insert(r.ref_id);
 		}
-		let root = self.root.clone().unwrap_or_else(PathBuf::new);
+		// Trait items are used if one of their implementations is
+		for d in self.defs.values() {
+			if let Some(decl_id) = d.decl_id.filter(|_| used_defs.contains(&d.id)) {
+				used_defs.insert(decl_id);
+			}
+		}
+		let root = self.root.clone().unwrap_or_default();
 		let mute_spans_cache = MuteSpansCache::new(root.as_path());
-		let mut unused_defs = self.defs.par_iter().filter_map(|(did, d)| {
-			if used_defs.contains(&did) {
+		self.defs.par_iter().filter_map(|(did, d)| {
+			if used_defs.contains(did) {
 				return None;
 			}
 			// Anything starting with _ can be unused without warning.
@@ -206,6 +265,10 @@ impl AnalysisDb {
 					return None;
 				}
 			}
+			// Items like `#[no_mangle]` functions are used from outside of Rust
+			if self.is_exported(d) {
+				return None;
+			}
 			// Macros have poor save-analysis support atm:
 			// https://github.com/rust-lang/rust/issues/49178#issuecomment-375454487
 			// Most importantly, their spans are not emitted.
@@ -213,8 +276,111 @@ impl AnalysisDb {
 				return None;
 			}
 			Some(d)
-		}).collect::<Vec<_>>();
-		unused_defs.sort();
-		unused_defs.into_iter()
+		}).collect()
+	}
+	fn is_exported(&self, d :&AbsDef) -> bool {
+		self.sources.get(&d.span.file_name)
+			.map(|info| info.exported.contains(&d.span.region().0))
+			.unwrap_or(false)
+	}
+	fn is_allowed(&self, d :&AbsDef) -> bool {
+		self.sources.is_allowed(&d.span.file_name, d.span.region())
+	}
+	/// Obtains the `#[expect(dead_code)]` attributes which don't apply to any of the unused definitions
+	fn unfulfilled_expectations(&self, unused_defs :&[&AbsDef]) -> Vec<Span> {
+		let files = self.defs.values()
+			.map(|d| d.span.file_name.as_str())
+			.collect::<HashSet<_>>();
+		let mut unfulfilled = Vec::new();
+		for file in files {
+			let Some(info) = self.sources.get(file) else {
+				continue
+			};
+			for expectation in &info.expectations {
+				let fulfilled = unused_defs.iter()
+					.flat_map(|d| self.sources.locations(&d.span.file_name, d.span.region()))
+					.any(|(def_file, region)| def_file == file && source::contains(expectation.region, region));
+				if !fulfilled {
+					let (line, column) = expectation.attr;
+					unfulfilled.push(Span {
+						file_name : file.to_owned(),
+						line_start : line as u32,
+						line_end : line as u32,
+						column_start : column as u32,
+						column_end : column as u32,
+					});
+				}
+			}
+		}
+		unfulfilled.sort();
+		unfulfilled
+	}
+}
+
+impl Backend for AnalysisDb {
+	fn get_findings(&self) -> Vec<Finding> {
+		let unused_defs = self.unsuppressed_unused_defs();
+		let mut findings = unused_defs.iter()
+			.filter(|d| !self.is_allowed(d))
+			.map(|d| Finding {
+				span : d.span.to_span(),
+				name : d.name.clone(),
+				kind : d.kind.clone(),
+				krate : self.crate_names.get(&d.id.krate).cloned(),
+				visibility : None,
+				reason : Reason::Unused,
+				severity : Severity::Warning,
+				symbol : None,
+				container : None,
+			})
+			.collect::<Vec<_>>();
+		// The crate of a file is the one of the definitions in it
+		let file_crates = self.defs.values()
+			.filter_map(|d| Some((d.span.file_name.as_str(), self.crate_names.get(&d.id.krate)?)))
+			.collect::<HashMap<_, _>>();
+		findings.extend(self.unfulfilled_expectations(&unused_defs).into_iter()
+			.map(|span| Finding {
+				krate : file_crates.get(span.file_name.as_str()).map(|c| c.to_string()),
+				span : span.to_span(),
+				name : String::new(),
+				kind : String::new(),
+				visibility : None,
+				reason : Reason::UnfulfilledExpectation,
+				severity : Severity::Warning,
+				symbol : None,
+				container : None,
+			}));
+		let mut files = file_crates.keys().collect::<Vec<_>>();
+		files.sort();
+		for file in files {
+			let Some(info) = self.sources.get(file) else {
+				continue
+			};
+			let stale = source::suppress(&info, file, &mut findings);
+			findings.extend(stale.into_iter()
+				.map(|(line, column)| Finding {
+					krate : file_crates.get(file).map(|c| c.to_string()),
+					span : Span {
+						file_name : file.to_string(),
+						line_start : line as u32,
+						line_end : line as u32,
+						column_start : column as u32,
+						column_end : column as u32,
+					}.to_span(),
+					name : String::new(),
+					kind : String::new(),
+					visibility : None,
+					reason : Reason::StaleSuppression,
+					severity : Severity::Warning,
+					symbol : None,
+					container : None,
+				}));
+		}
+		self.options.apply(&mut findings);
+		findings.sort();
+		findings
+	}
+	fn skipped(&self) -> &Skipped {
+		&self.skipped
 	}
 }
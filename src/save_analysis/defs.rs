@@ -30,6 +30,19 @@ impl Span {
 	pub fn display_str(&self) -> String {
 		format!("{}:{}:{}", self.file_name, self.line_start, self.column_start)
 	}
+	pub fn to_span(&self) -> crate::finding::Span {
+		crate::finding::Span {
+			file : self.file_name.as_str().into(),
+			start_line : self.line_start,
+			start_col : self.column_start,
+			end_line : self.line_end,
+			end_col : self.column_end,
+		}
+	}
+	pub fn region(&self) -> crate::source::Region {
+		((self.line_start as usize, self.column_start as usize),
+			(self.line_end as usize, self.column_end as usize))
+	}
 }
 
 #[derive(Deserialize, Debug)]
@@ -52,6 +65,9 @@ pub struct Def<KrateId> {
 	pub span :Span,
 	pub parent :Option<ItemId<KrateId>>,
 	pub decl_id :Option<ItemId<KrateId>>,
+	/// For modules, the file they are defined in
+	#[serde(default)]
+	pub value :String,
 }
 
 
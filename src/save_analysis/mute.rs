@@ -1,5 +1,5 @@
 use super::defs::CrateDisambiguator;
-use crate::StrErr;
+use crate::Error;
 use std::path::{Path, PathBuf};
 use std::iter::FromIterator;
 use intervaltree::IntervalTree;
@@ -65,7 +65,7 @@ impl MuteSpansCache {
 			cache : CHashMap::new(),
 		}
 	}
-	pub fn is_in_macro(&self, crate_id :CrateDisambiguator, needle_span :&super::defs::Span) -> Result<bool, StrErr> {
+	pub fn is_in_macro(&self, crate_id :CrateDisambiguator, needle_span :&super::defs::Span) -> Result<bool, Error> {
 		if let Some(mute_spans) = self.cache.get(&(crate_id, needle_span.file_name.clone())) {
 			return Ok(in_mute_spans(&mute_spans, needle_span));
 		}
@@ -80,7 +80,7 @@ impl MuteSpansCache {
 	}
 }
 
-fn mute_spans_for_file<'a>(file :&str) -> Result<MuteSpans, StrErr> {
+fn mute_spans_for_file(file :&str) -> Result<MuteSpans, Error> {
 	use syn::parse::Parser;
 	use syn::parse::ParseStream;
 	use syn::{Attribute, Item, Macro, ItemFn, Ident};
@@ -170,8 +170,8 @@ fn mute_spans_for_file<'a>(file :&str) -> Result<MuteSpans, StrErr> {
 		let mut visitor = Visitor {
 			mute_spans : &mut mute_spans_vec,
 		};
-		visit_item(&mut visitor, &item);
+		visit_item(&mut visitor, item);
 	}
 	let mute_spans = MuteSpans::from_iter(mute_spans_vec);
-	return Ok(mute_spans);
+	Ok(mute_spans)
 }
@@ -0,0 +1,190 @@
+use scip::types::{Index, Occurrence, SymbolInformation};
+use serde::Serialize;
+
+use super::{item_path, Roles, Span};
+use crate::{config::Patterns, Error};
+use std::{collections::HashMap, io::Write, sync::Arc};
+
+/// Restricts the contents of a dump
+#[derive(Default)]
+pub struct DumpFilter {
+	/// Only dump the documents whose path matches
+	pub documents: Patterns,
+	/// Only dump symbols starting with one of the prefixes,
+	/// either as SCIP symbol or as path like `krate::module`
+	pub symbol_prefixes: Vec<String>,
+	/// Only dump symbols of these kinds, like `Function`
+	pub kinds: Vec<String>,
+}
+
+impl DumpFilter {
+	fn matches_symbol(&self, sym: &str, info: Option<&SymbolInformation>) -> bool {
+		let prefix_matches = self.symbol_prefixes.is_empty() || self.symbol_prefixes.iter()
+			.any(|prefix| sym.starts_with(prefix.as_str())
+				|| item_path(sym).map(|path| path.starts_with(prefix.as_str())).unwrap_or(false));
+		let kind_matches = self.kinds.is_empty() || info.and_then(kind_name)
+			.map(|kind| self.kinds.iter().any(|k| k.eq_ignore_ascii_case(&kind)))
+			.unwrap_or(false);
+		prefix_matches && kind_matches
+	}
+	/// Whether documents are dropped if nothing inside them matches
+	fn filters_symbols(&self) -> bool {
+		!self.symbol_prefixes.is_empty() || !self.kinds.is_empty()
+	}
+}
+
+/// The contents of a SCIP index, as emitted by the indexer
+#[derive(Serialize)]
+pub struct Dump {
+	pub project_root: String,
+	pub documents: Vec<DumpDocument>,
+	pub external_symbols: Vec<DumpSymbol>,
+}
+
+#[derive(Serialize)]
+pub struct DumpDocument {
+	pub path: String,
+	pub symbols: Vec<DumpSymbol>,
+	pub occurrences: Vec<DumpOccurrence>,
+}
+
+#[derive(Serialize)]
+pub struct DumpSymbol {
+	pub symbol: String,
+	/// The symbol as Rust path, like `krate::module::Item`
+	pub path: Option<String>,
+	pub display_name: String,
+	pub kind: Option<String>,
+	pub signature: Option<String>,
+	pub relationships: Vec<DumpRelationship>,
+}
+
+#[derive(Serialize)]
+pub struct DumpRelationship {
+	pub symbol: String,
+	/// The kinds of the relationship, like `implementation` or `reference`
+	pub kinds: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct DumpOccurrence {
+	pub symbol: String,
+	pub span: Span,
+	/// The decoded roles, like `definition` or `import`
+	pub roles: Vec<&'static str>,
+	pub enclosing_span: Option<Span>,
+}
+
+fn kind_name(info: &SymbolInformation) -> Option<String> {
+	info.kind.enum_value().ok().map(|kind| format!("{kind:?}"))
+}
+
+fn dump_symbol(info: &SymbolInformation) -> DumpSymbol {
+	DumpSymbol {
+		symbol: info.symbol.clone(),
+		path: item_path(&info.symbol),
+		display_name: info.display_name.clone(),
+		kind: kind_name(info),
+		signature: info.signature_documentation.as_ref().map(|sig| sig.text.clone()),
+		relationships: info.relationships.iter()
+			.map(|rel| DumpRelationship {
+				symbol: rel.symbol.clone(),
+				kinds: [
+					(rel.is_implementation, "implementation"),
+					(rel.is_reference, "reference"),
+					(rel.is_type_definition, "type-definition"),
+					(rel.is_definition, "definition"),
+				].iter()
+					.filter(|(is, _kind)| *is)
+					.map(|(_is, kind)| *kind)
+					.collect(),
+			})
+			.collect(),
+	}
+}
+
+fn dump_occurrence(file: &Arc<str>, occ: &Occurrence) -> Option<DumpOccurrence> {
+	let span = match Span::from_scip_range(file, &occ.range) {
+		Ok(span) => span,
+		Err(e) => {
+			warn!("Skipping occurrence of {} in {}: {}", occ.symbol, file, e);
+			return None;
+		},
+	};
+	Some(DumpOccurrence {
+		symbol: occ.symbol.clone(),
+		span,
+		roles: Roles(occ.symbol_roles).names(),
+		enclosing_span: Span::from_scip_range(file, &occ.enclosing_range).ok(),
+	})
+}
+
+impl Dump {
+	/// Obtains the contents of the index that match the filter
+	pub fn new(index: &Index, filter: &DumpFilter) -> Dump {
+		let infos = index.documents.iter()
+			.flat_map(|doc| &doc.symbols)
+			.chain(&index.external_symbols)
+			.map(|info| (info.symbol.as_str(), info))
+			.collect::<HashMap<_, _>>();
+		let documents = index.documents.iter()
+			.filter(|doc| filter.documents.is_empty() || filter.documents.is_match(&doc.relative_path))
+			.map(|doc| {
+				let file: Arc<str> = Arc::from(doc.relative_path.as_str());
+				DumpDocument {
+					path: doc.relative_path.clone(),
+					symbols: doc.symbols.iter()
+						.filter(|info| filter.matches_symbol(&info.symbol, Some(info)))
+						.map(dump_symbol)
+						.collect(),
+					occurrences: doc.occurrences.iter()
+						.filter(|occ| filter.matches_symbol(&occ.symbol, infos.get(occ.symbol.as_str()).copied()))
+						.filter_map(|occ| dump_occurrence(&file, occ))
+						.collect(),
+				}
+			})
+			.filter(|doc| !filter.filters_symbols() || !doc.symbols.is_empty() || !doc.occurrences.is_empty())
+			.collect();
+		// External symbols don't belong to any document
+		let external_symbols = if filter.documents.is_empty() {
+			index.external_symbols.iter()
+				.filter(|info| filter.matches_symbol(&info.symbol, Some(info)))
+				.map(dump_symbol)
+				.collect()
+		} else {
+			Vec::new()
+		};
+		Dump {
+			project_root: index.metadata.project_root.clone(),
+			documents,
+			external_symbols,
+		}
+	}
+	pub fn write_text(&self, w: &mut dyn Write) -> Result<(), Error> {
+		writeln!(w, "project root: {}", self.project_root)?;
+		for doc in &self.documents {
+			writeln!(w, "document: {}", doc.path)?;
+			for sym in &doc.symbols {
+				sym.write_text(w, "  symbol")?;
+			}
+			for occ in &doc.occurrences {
+				writeln!(w, "  occurrence {} [{}] {}", occ.span.display_str(), occ.roles.join(", "), occ.symbol)?;
+			}
+		}
+		for sym in &self.external_symbols {
+			sym.write_text(w, "external symbol")?;
+		}
+		Ok(())
+	}
+}
+
+impl DumpSymbol {
+	fn write_text(&self, w: &mut dyn Write, prefix: &str) -> Result<(), Error> {
+		let kind = self.kind.as_deref().unwrap_or("<unknown>");
+		writeln!(w, "{} {} '{}' {}", prefix, kind, self.display_name, self.symbol)?;
+		for rel in &self.relationships {
+			writeln!(w, "    {} {}", rel.kinds.join(", "), rel.symbol)?;
+		}
+		Ok(())
+	}
+}
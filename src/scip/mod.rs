@@ -1,32 +1,34 @@
-use protobuf::Message;
-use scip::{symbol::parse_symbol, types::{symbol_information, Index, Symbol, SymbolRole}};
+mod dump;
 
-use crate::{StrErr, Options};
+pub use dump::{Dump, DumpFilter};
+
+use protobuf::{CodedInputStream, Message};
+use scip::{symbol::{format_symbol_with, is_local_symbol, parse_symbol, SymbolFormatOptions}, types::{descriptor::Suffix, symbol_information, Descriptor, Index, Symbol, SymbolRole}};
+use intervaltree::IntervalTree;
+
+use crate::{finding::{Backend, Finding, Reason, Severity, Visibility}, metadata::Metadata, error::Skipped, source::{self, SourceCache}, Error, Options};
+use rayon::prelude::*;
 use core::{cmp::Ordering, fmt::{Debug, Formatter}, write};
-use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, process::Command, sync::Arc};
+use std::{collections::{HashMap, HashSet, VecDeque}, io::Write, iter::FromIterator, path::{Path, PathBuf}, process::Command, sync::Arc};
 
-fn parse_scip_index(path: &Path) -> Result<Index, StrErr> {
-	println!("parsing {path:?}");
+pub fn parse_scip_index(path: &Path) -> Result<Index, Error> {
+	info!("parsing {path:?}");
 	let mut file = std::fs::File::open(path)?;
 	let index = Index::parse_from_reader(&mut file)?;
 	Ok(index)
 }
 
-#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Span {
-	pub file: Arc<str>,
-	pub start_line: u32,
-	pub start_col: u32,
-	pub end_line: u32,
-	pub end_col: u32,
-}
+pub use crate::finding::Span;
 
 impl Span {
-	fn from_scip_range(file: &Arc<str>, range: &[i32]) -> Result<Span, StrErr> {
+	fn from_scip_range(file: &Arc<str>, range: &[i32]) -> Result<Span, Error> {
 		// https://docs.rs/scip/latest/scip/types/struct.Occurrence.html#structfield.range
-		let range_one_based = range.iter().map(|v| *v as u32 + 1).collect::<Vec<_>>();
-		let span = match &range_one_based[..] {
-			&[start_line, start_col, end_line, end_col] => {
+		let range_one_based = range.iter()
+			.map(|v| u32::try_from(*v).ok().and_then(|v| v.checked_add(1)))
+			.collect::<Option<Vec<_>>>()
+			.ok_or_else(|| Error::Range(range.to_vec()))?;
+		let span = match range_one_based[..] {
+			[start_line, start_col, end_line, end_col] => {
 				Span {
 					file: file.clone(),
 					start_line,
@@ -35,7 +37,7 @@ impl Span {
 					end_col,
 				}
 			}
-			&[line, start_col, end_col] => {
+			[line, start_col, end_col] => {
 				Span {
 					file: file.clone(),
 					start_line: line,
@@ -44,17 +46,13 @@ impl Span {
 					end_col,
 				}
 			}
-			_ => {
-				Err(format!("range has wrong number of arguments: {}", range.len()))?
-			}
+			_ => return Err(Error::Range(range.to_vec())),
 		};
 		Ok(span)
 	}
-	pub fn display_str(&self) -> String {
-		format!("{}:{}:{}", self.file, self.start_line, self.start_col)
-	}
 }
 
+#[derive(Clone, Copy)]
 pub struct Roles(i32);
 
 impl Roles {
@@ -80,47 +78,35 @@ impl Roles {
 	pub fn is_forward_definition(&self) -> bool {
 		self.0 & SymbolRole::ForwardDefinition as i32 > 0
 	}
-}
-
-fn shorten_symbol(symbol: &Symbol) -> String {
-	let package_name = &symbol.package.name;
-	let descriptors = symbol.descriptors.iter()
-		.map(|d| format!("{}_{:?}", d.name, d.suffix))
-		.collect::<Vec<_>>();
-	let descriptor_str = descriptors.join(":");
-	format!("{package_name}::{descriptor_str}")
-}
-
-fn dump_index(index: &Index) -> Result<(), StrErr> {
-	println!("index absolute path: {}", index.metadata.project_root);
-	for sym in &index.external_symbols {
-		let symbol = parse_symbol(&sym.symbol).unwrap();
-		let symbol_short = shorten_symbol(&symbol);
-		println!("  ext sym '{}' kind '{:?}' {}", sym.display_name, sym.kind, symbol_short);
-		for rel in &sym.relationships {
-			println!("    {:?}", rel);
+	/// Obtains the names of the roles, or `reference` if there are none
+	pub fn names(&self) -> Vec<&'static str> {
+		let roles = [
+			(self.is_definition(), "definition"),
+			(self.is_forward_definition(), "forward-definition"),
+			(self.is_import(), "import"),
+			(self.is_read_access(), "read"),
+			(self.is_write_access(), "write"),
+			(self.is_generated(), "generated"),
+			(self.is_test(), "test"),
+		];
+		let names = roles.iter()
+			.filter(|(is, _name)| *is)
+			.map(|(_is, name)| *name)
+			.collect::<Vec<_>>();
+		if names.is_empty() {
+			vec!["reference"]
+		} else {
+			names
 		}
 	}
-	for doc in &index.documents {
-		let path_arc: Arc<str> = Arc::from(doc.relative_path.clone().into_boxed_str());
-		println!("path: {}", doc.relative_path);
-		for sym in &doc.symbols {
-			let symbol = parse_symbol(&sym.symbol).unwrap();
-			let symbol_short = shorten_symbol(&symbol);
-			println!("  sym '{}' kind '{:?}' {}", sym.display_name, sym.kind, symbol_short);
-			for rel in &sym.relationships {
-				println!("    {:?}", rel);
-			}
-		}
-		for occ in &doc.occurrences {
-			let sp = Span::from_scip_range(&path_arc, &occ.range)?;
-			let symbol = parse_symbol(&occ.symbol).unwrap();
-			let symbol_short = shorten_symbol(&symbol);
-			println!("  occ '{}' span '{}' roles {}", symbol_short, sp.display_str(), occ.symbol_roles);
+}
 
-		}
-	}
-	Ok(())
+/// Parses the symbol, unlike `parse_symbol` with an error that can be displayed
+fn try_parse_symbol(sym: &str) -> Result<Symbol, Error> {
+	parse_symbol(sym).map_err(|e| Error::Symbol {
+		symbol: sym.to_owned(),
+		message: format!("{e:?}"),
+	})
 }
 
 #[derive(Clone, PartialEq, Eq, Copy)]
@@ -138,7 +124,7 @@ impl Debug for Kind {
 }
 impl PartialOrd for Kind {
 	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-		(self.0 as i32).partial_cmp(&(other.0 as i32))
+		Some(self.cmp(other))
 	}
 }
 impl Ord for Kind {
@@ -152,100 +138,1351 @@ pub struct AbsDef {
 	pub span: Span,
 	pub name: Option<String>,
 	pub kind: Option<Kind>,
+	pub visibility: Option<Visibility>,
+}
+
+/// An occurrence of a symbol that is not its definition
+#[derive(Clone)]
+pub struct Use {
+	pub span: Span,
+	pub roles: Roles,
+	/// The innermost definition whose enclosing range contains the use
+	pub container: Option<String>,
+}
+
+impl Use {
+	/// Whether the occurrence is an actual use, and not only an import or generated by a macro
+	pub fn is_actual(&self) -> bool {
+		!self.roles.is_import() && !self.roles.is_generated()
+	}
+}
+
+/// Obtains the trait's name from symbols of trait impl items like `impl#[Type][Trait]method().`
+fn impl_trait_name(symbol: &Symbol) -> Option<&str> {
+	let descriptors = &symbol.descriptors;
+	let impl_pos = descriptors.iter()
+		.rposition(|d| d.name == "impl" && d.suffix.enum_value() == Ok(Suffix::Type))?;
+	match &descriptors[impl_pos + 1..] {
+		[ty, tr, _item] if ty.suffix.enum_value() == Ok(Suffix::TypeParameter)
+				&& tr.suffix.enum_value() == Ok(Suffix::TypeParameter) => {
+			Some(&tr.name)
+		}
+		_ => None,
+	}
+}
+
+/// Obtains the name of the package the symbol is defined in
+fn package_name(sym: &str) -> Option<String> {
+	parse_symbol(sym).ok()
+		.map(|symbol| symbol.package.name.clone())
+		.filter(|name| !name.is_empty())
+}
+
+/// Obtains the names of the leading modules of the descriptors
+fn namespaces(descriptors: &[Descriptor]) -> Vec<String> {
+	descriptors.iter()
+		.take_while(|d| d.suffix.enum_value() == Ok(Suffix::Namespace))
+		.map(|d| d.name.clone())
+		.collect()
+}
+
+/// Obtains the names of the modules the symbol is nested in, from the crate root on,
+/// including the symbol itself if it is a module
+fn module_path(sym: &str) -> Option<Vec<String>> {
+	let symbol = parse_symbol(sym).ok()?;
+	Some(namespaces(&symbol.descriptors))
+}
+
+/// Obtains the names of the modules the symbol is defined in, from the crate root on
+fn parent_module_path(sym: &str) -> Option<Vec<String>> {
+	let symbol = parse_symbol(sym).ok()?;
+	let (_last, parents) = symbol.descriptors.split_last()?;
+	Some(namespaces(parents))
+}
+
+/// Removes the package version from the symbol, so that it stays the same across releases
+pub fn symbol_without_version(sym: &str) -> String {
+	let Ok(symbol) = parse_symbol(sym) else {
+		return sym.to_owned()
+	};
+	let options = SymbolFormatOptions {
+		include_scheme: true,
+		include_package_manager: true,
+		include_package_name: true,
+		include_package_version: false,
+		include_descriptor: true,
+	};
+	format_symbol_with(symbol, options)
+}
+
+/// Obtains a Rust path like `krate::module::Type::method` for the symbol
+///
+/// Items of impl blocks are put below the implementing type.
+pub fn item_path(sym: &str) -> Option<String> {
+	if is_local_symbol(sym) {
+		return None;
+	}
+	let symbol = parse_symbol(sym).ok()?;
+	let mut path = vec![symbol.package.name.replace('-', "_")];
+	let mut in_impl = false;
+	for (i, d) in symbol.descriptors.iter().enumerate() {
+		let suffix = d.suffix.enum_value();
+		if i == 0 && d.name == "crate" && suffix == Ok(Suffix::Namespace) {
+			continue;
+		}
+		if d.name == "impl" && suffix == Ok(Suffix::Type) {
+			in_impl = true;
+			continue;
+		}
+		match suffix {
+			// The first type parameter of an impl is the implementing type, the second the trait
+			Ok(Suffix::TypeParameter) if in_impl => {
+				path.push(d.name.clone());
+				in_impl = false;
+			},
+			Ok(Suffix::TypeParameter | Suffix::Parameter | Suffix::Meta | Suffix::Local) => (),
+			_ if d.name.is_empty() => (),
+			_ => path.push(d.name.clone()),
+		}
+	}
+	Some(path.join("::"))
+}
+
+/// Whether the symbol is a method or an item inside of an impl block
+fn is_assoc_item(symbol: &Symbol) -> bool {
+	let in_impl = symbol.descriptors.iter()
+		.any(|d| d.name == "impl" && d.suffix.enum_value() == Ok(Suffix::Type));
+	let is_method = symbol.descriptors.last()
+		.map(|d| d.suffix.enum_value() == Ok(Suffix::Method))
+		.unwrap_or(false);
+	in_impl || is_method
+}
+
+/// Local symbols like `local 3` are only unique within their document
+fn symbol_key(doc_path: &str, symbol: &str) -> String {
+	if is_local_symbol(symbol) {
+		format!("{symbol} {doc_path}")
+	} else {
+		symbol.to_owned()
+	}
+}
+
+/// The enclosing ranges of the non-local definitions inside a document
+struct Containers {
+	inner: IntervalTree<(u32, u32), (Span, String)>,
+}
+
+impl Containers {
+	/// Finds the innermost definition enclosing the span, other than `symbol` itself
+	fn innermost(&self, span: &Span, symbol: &str) -> Option<&str> {
+		self.inner.query_point(span.start())
+			.map(|el| &el.value)
+			.filter(|(enclosing, sym)| enclosing.end() >= span.end() && sym != symbol)
+			.max_by(|(a, _), (b, _)| a.start().cmp(&b.start()).then(b.end().cmp(&a.end())))
+			.map(|(_, sym)| sym.as_str())
+	}
+}
+
+impl FromIterator<(Span, String)> for Containers {
+	fn from_iter<T: IntoIterator<Item = (Span, String)>>(iter: T) -> Self {
+		Self {
+			inner: iter.into_iter()
+				.map(|(span, sym)| intervaltree::Element {
+					range: span.start()..span.end(),
+					value: (span, sym),
+				})
+				.collect(),
+		}
+	}
+}
+
+/// Knows which code is only compiled for tests
+struct TestCode<'a> {
+	db: &'a AnalysisDb,
+	/// Files of integration tests and benchmarks
+	files: HashSet<&'a str>,
+	/// Packages and paths of modules that are only compiled for tests
+	modules: Vec<(Option<String>, Vec<String>)>,
+}
+
+impl<'a> TestCode<'a> {
+	fn new(db: &'a AnalysisDb) -> Self {
+		let mut files = HashSet::new();
+		if let Some(root) = &db.root {
+			let test_targets = db.metadata.iter()
+				.flat_map(|m| m.targets())
+				.filter(|t| t.kind.iter().any(|k| k == "test" || k == "bench"))
+				.filter_map(|t| t.src_path.strip_prefix(root).ok())
+				.collect::<Vec<_>>();
+			for doc in &db.index.documents {
+				let file = Path::new(&doc.relative_path);
+				// Integration tests can have modules in the tests directory as well
+				let is_test_file = test_targets.iter()
+					.any(|target| *target == file || target.parent()
+						.filter(|dir| dir.ends_with("tests") || dir.ends_with("benches"))
+						.map(|dir| file.starts_with(dir))
+						.unwrap_or(false));
+				if is_test_file {
+					files.insert(doc.relative_path.as_str());
+				}
+			}
+		}
+		let mut test_code = TestCode {
+			db,
+			files,
+			modules: Vec::new(),
+		};
+		// Modules declared by `#[cfg(test)] mod tests;` or `#[cfg(test)] mod tests { ... }`
+		let is_test_decl = |u: &Use| test_code.is_test_span(&u.span) && db.sources.get(&u.span.file)
+			.map(|info| info.mod_decls.contains(&u.span.region().0))
+			.unwrap_or(false);
+		let modules = db.definitions.iter()
+			.filter(|(_sym, def)| def.kind.map(|k| k.0) == Some(symbol_information::Kind::Module))
+			.filter(|(sym, def)| test_code.is_test_span(&def.span)
+				|| db.uses.get(*sym).into_iter().flatten().any(is_test_decl))
+			.filter_map(|(sym, _def)| Some((package_name(sym), module_path(sym)?)))
+			.collect();
+		test_code.modules = modules;
+		test_code
+	}
+	fn is_test_span(&self, span: &Span) -> bool {
+		self.files.contains(&*span.file) || self.db.sources.get(&span.file)
+			.map(|info| info.is_test(span.region()))
+			.unwrap_or(false)
+	}
+	/// Whether the definition is part of test code
+	fn is_test_sym(&self, sym: &str) -> bool {
+		if self.db.definitions.get(sym).map(|def| self.is_test_span(&def.span)).unwrap_or(false) {
+			return true;
+		}
+		let Some(path) = module_path(sym) else {
+			return false
+		};
+		let package = package_name(sym);
+		self.modules.iter()
+			.any(|(p, m)| *p == package && path.starts_with(m))
+	}
+	fn is_test_use(&self, u: &Use) -> bool {
+		u.roles.is_test() || self.is_test_span(&u.span)
+			|| u.container.as_deref().map(|c| self.is_test_sym(c)).unwrap_or(false)
+	}
 }
 
 pub struct AnalysisDb {
 	options :Options,
 	root :Option<PathBuf>,
+	/// The cargo metadata of the workspaces of the indexes
+	metadata: Vec<Metadata>,
 	index: Index,
 	definitions: HashMap<String, AbsDef>,
+	/// The innermost non-local definition enclosing each definition,
+	/// or for out-of-line modules the module containing their `mod name;` declaration
+	parents: HashMap<String, String>,
+	uses: HashMap<String, Vec<Use>>,
+	/// Entry points like `main` or `#[test]` functions, which are used implicitly
+	roots: HashSet<String>,
+	/// Items of trait impls, mapped to the corresponding items of the traits in the workspace.
+	/// If the list is empty, the trait is from outside of the workspace.
+	trait_impls: HashMap<String, Vec<String>>,
+	sources: SourceCache,
+	/// Entries of the index that were skipped because they were malformed
+	skipped: Skipped,
+}
+
+fn project_root(index: &Index) -> Option<PathBuf> {
+	let root = index.metadata.project_root.strip_prefix("file://")?;
+	Some(PathBuf::from(root))
+}
+
+/// Reads the project root from the metadata of the index, without parsing the documents
+pub fn index_project_root(path: &Path) -> Result<Option<PathBuf>, Error> {
+	let mut file = std::fs::File::open(path)?;
+	let mut input = CodedInputStream::new(&mut file);
+	// The metadata is the first field of the index
+	const METADATA_TAG: u32 = 1 << 3 | 2;
+	while let Some(tag) = input.read_raw_tag_or_eof()? {
+		if tag == METADATA_TAG {
+			let metadata = input.read_message::<scip::types::Metadata>()?;
+			let root = metadata.project_root.strip_prefix("file://").map(PathBuf::from);
+			return Ok(root);
+		}
+		protobuf::rt::skip_field_for_tag(tag, &mut input)?;
+	}
+	Ok(None)
+}
+
+/// Obtains the longest path that both paths start with
+fn common_ancestor(a: &Path, b: &Path) -> PathBuf {
+	a.components()
+		.zip(b.components())
+		.take_while(|(a, b)| a == b)
+		.map(|(a, _b)| a)
+		.collect()
+}
+
+/// Merges the indexes into one, relative to the common ancestor of their project roots
+///
+/// Documents and external symbols that are contained in multiple indexes are only kept once.
+pub fn merge_indexes(mut indexes: Vec<Index>) -> Result<Index, Error> {
+	if indexes.len() == 1 {
+		return Ok(indexes.remove(0));
+	}
+	let roots = indexes.iter()
+		.map(|index| project_root(index)
+			.ok_or_else(|| Error::Unsupported(format!("index has unsupported project root '{}'", index.metadata.project_root))))
+		.collect::<Result<Vec<_>, _>>()?;
+	let Some(common_root) = roots.iter()
+		.map(|root| root.to_owned())
+		.reduce(|a, b| common_ancestor(&a, &b)) else {
+		return Err(Error::InvalidArgument("no indexes given".to_owned()));
+	};
+	let mut merged = Index::new();
+	merged.metadata = indexes[0].metadata.clone();
+	merged.metadata.mut_or_insert_default().project_root = format!("file://{}", common_root.display());
+	let mut seen_documents = HashSet::new();
+	let mut seen_symbols = HashSet::new();
+	for (index, root) in indexes.into_iter().zip(roots) {
+		for mut doc in index.documents {
+			let path = root.join(&doc.relative_path);
+			let Ok(relative_path) = path.strip_prefix(&common_root) else {
+				continue
+			};
+			doc.relative_path = relative_path.to_string_lossy().into_owned();
+			if seen_documents.insert(doc.relative_path.clone()) {
+				merged.documents.push(doc);
+			}
+		}
+		for sym in index.external_symbols {
+			if seen_symbols.insert(sym.symbol.clone()) {
+				merged.external_symbols.push(sym);
+			}
+		}
+	}
+	Ok(merged)
 }
 
 impl AnalysisDb {
-	pub fn from_path(path :&str, options :Options) -> Result<Self, StrErr> {
-		let path = Path::new(path);
-		let index = parse_scip_index(path)?;
+	pub fn from_path(path :&Path, options :Options) -> Result<Self, Error> {
+		Self::from_paths(&[path], options)
+	}
+	/// Analyzes multiple indexes together, like the ones of several workspaces
+	/// that depend on each other
+	pub fn from_paths(paths :&[&Path], options :Options) -> Result<Self, Error> {
+		let indexes = paths.iter()
+			.map(|path| parse_scip_index(path))
+			.collect::<Result<Vec<_>, _>>()?;
+		let workspace_roots = indexes.iter()
+			.filter_map(project_root)
+			.collect::<Vec<_>>();
+		let index = merge_indexes(indexes)?;
 		info!("parsed scip file. found {} documents", index.documents.len());
-		let root = path.parent()
-			.and_then(|p| p.parent())
-			.and_then(|p| p.parent())
-			.and_then(|p| p.parent())
-			.and_then(|p| p.parent())
-			.map(|p| p.to_owned());
+		let root = project_root(&index);
+		let metadata = workspace_roots.iter()
+			.filter_map(|root| match Metadata::for_dir(root) {
+				Ok(metadata) => Some(metadata),
+				Err(e) => {
+					warn!("Couldn't obtain cargo metadata for {}: {}", root.display(), e);
+					None
+				},
+			})
+			.collect();
+		let mut sources = SourceCache::new(root.as_deref().unwrap_or(Path::new("")));
+		// Scan the documents in parallel, later lookups are served from the cache
+		index.documents.par_iter()
+			.for_each(|doc| {
+				sources.get(&doc.relative_path);
+			});
 		let mut definitions = HashMap::new();
+		let mut parents = HashMap::new();
+		let mut uses = HashMap::<_, Vec<_>>::new();
+		let mut relationships = Vec::new();
+		// Malformed entries are skipped instead of aborting the entire analysis
+		let mut skipped = Skipped::default();
 		for doc in &index.documents {
-			let sym_name_kinds = doc.symbols.iter()
-				.map(|sym| {
-					(sym.symbol.clone(), (sym.display_name.clone(), sym.kind.enum_value().ok()))
-				})
+			for sym in &doc.symbols {
+				for rel in &sym.relationships {
+					if rel.is_implementation || rel.is_reference {
+						relationships.push((sym.symbol.clone(), rel.symbol.clone()));
+					}
+				}
+			}
+			let sym_infos = doc.symbols.iter()
+				.map(|sym| (sym.symbol.as_str(), sym))
 				.collect::<HashMap<_,_>>();
 			let path_arc: Arc<str> = Arc::from(doc.relative_path.clone().into_boxed_str());
+			let source_info = sources.get(&doc.relative_path);
+			let containers = doc.occurrences.iter()
+				.filter(|occ| Roles(occ.symbol_roles).is_definition() && !is_local_symbol(&occ.symbol))
+				.filter(|occ| !occ.enclosing_range.is_empty())
+				.filter_map(|occ| match Span::from_scip_range(&path_arc, &occ.enclosing_range) {
+					Ok(span) => Some((span, occ.symbol.clone())),
+					Err(e) => {
+						warn!("Ignoring enclosing range of {} in {}: {}", occ.symbol, doc.relative_path, e);
+						skipped.add("invalid enclosing ranges");
+						None
+					},
+				})
+				.collect::<Containers>();
 			for occ in &doc.occurrences {
-				if occ.symbol_roles & SymbolRole::Definition as i32 == 0 {
+				let span = match Span::from_scip_range(&path_arc, &occ.range) {
+					Ok(span) => span,
+					Err(e) => {
+						warn!("Skipping occurrence of {} in {}: {}", occ.symbol, doc.relative_path, e);
+						skipped.add("occurrences with invalid ranges");
+						continue;
+					},
+				};
+				if let Err(e) = try_parse_symbol(&occ.symbol) {
+					warn!("Skipping occurrence in {}: {}", span.display_str(), e);
+					skipped.add("occurrences with malformed symbols");
+					continue;
+				}
+				let key = symbol_key(&doc.relative_path, &occ.symbol);
+				let container = containers.innermost(&span, &occ.symbol)
+					.map(|sym| sym.to_owned());
+				if !Roles(occ.symbol_roles).is_definition() {
+					// rust-analyzer doesn't set the import role, so find the names imported
+					// by `use` items ourselves. The paths leading to them are actual uses.
+					let is_import_name = source_info.as_ref()
+						.map(|info| info.imports.iter().any(|i| !i.glob && i.pos == span.region().0))
+						.unwrap_or(false);
+					let import_role = if is_import_name { SymbolRole::Import as i32 } else { 0 };
+					// Neither does it set the access roles. Patterns only read
+					// their enum variants, while struct expressions write fields.
+					let pos = span.region().0;
+					let mut roles = occ.symbol_roles | import_role;
+					if let Some(info) = &source_info {
+						if info.writes.contains(&pos) {
+							roles |= SymbolRole::WriteAccess as i32;
+						}
+						if info.pattern_paths.contains(&pos) {
+							roles |= SymbolRole::ReadAccess as i32;
+						}
+						// Code generated by derives isn't a real use, unlike the derive macro's path
+						let in_derive = info.derives.iter().any(|r| source::contains(*r, span.region()));
+						if in_derive && !info.derive_paths.contains(&pos) {
+							roles |= SymbolRole::Generated as i32;
+						}
+					}
+					uses.entry(key).or_default().push(Use {
+						span,
+						roles: Roles(roles),
+						container,
+					});
 					continue;
 				}
-				let name_kind = sym_name_kinds.get(&occ.symbol);
+				let sym_info = sym_infos.get(occ.symbol.as_str());
+				let visibility = sym_info
+					.filter(|_| !is_local_symbol(&occ.symbol))
+					.and_then(|info| info.signature_documentation.as_ref())
+					.map(|sig| Visibility::from_signature(&sig.text));
 				let abs_def = AbsDef {
-					span: Span::from_scip_range(&path_arc, &occ.range)?,
-					name: name_kind.map(|(name, _kind)| name.clone()),
-					kind: name_kind.and_then(|(_name, kind)| kind.map(|kind| Kind(kind)).clone()),
+					span,
+					name: sym_info.map(|info| info.display_name.clone()),
+					kind: sym_info.and_then(|info| info.kind.enum_value().ok().map(Kind)),
+					visibility,
 				};
-				let symbol = parse_symbol(&occ.symbol).unwrap();
-				trace!("Adding def {}", shorten_symbol(&symbol));
-				definitions.insert(occ.symbol.clone(), abs_def);
+				trace!("Adding def {}", occ.symbol);
+				if let Some(container) = container {
+					parents.insert(key.clone(), container);
+				}
+				definitions.insert(key, abs_def);
+			}
+		}
+		// Enum variants have the visibility of their enum
+		for (sym, parent) in &parents {
+			let is_variant = definitions.get(sym)
+				.and_then(|def| def.kind)
+				.map(|kind| kind.0 == symbol_information::Kind::EnumMember)
+				.unwrap_or(false);
+			if is_variant {
+				let visibility = definitions.get(parent).and_then(|def| def.visibility);
+				if let Some(def) = definitions.get_mut(sym) {
+					def.visibility = visibility;
+				}
+			}
+		}
+		// The module's definition is in its file, the `mod name;` declaration is a use.
+		// The declaration is what puts the module into its parent module.
+		for (sym, module_uses) in &uses {
+			let Some(def) = definitions.get(sym) else {
+				continue
+			};
+			if def.kind.map(|k| k.0) != Some(symbol_information::Kind::Module) {
+				continue;
+			}
+			for u in module_uses {
+				let pos = u.span.region().0;
+				let is_decl = sources.get(&u.span.file)
+					.map(|info| info.mod_decls.contains(&pos))
+					.unwrap_or(false);
+				if is_decl {
+					sources.add_mod_decl(&def.span.file, &u.span.file, pos);
+					if let Some(container) = &u.container {
+						parents.entry(sym.clone()).or_insert_with(|| container.clone());
+					}
+				}
 			}
 		}
-		Ok(AnalysisDb {
+		let mut db = AnalysisDb {
 			options,
 			root,
+			metadata,
 			index,
 			definitions,
-		})
+			parents,
+			uses,
+			roots: HashSet::new(),
+			trait_impls: HashMap::new(),
+			sources,
+			skipped,
+		};
+		db.roots = db.find_roots();
+		db.trait_impls = db.find_trait_impls(relationships);
+		Ok(db)
+	}
+	/// Finds the items of trait impls and links them to the trait's items
+	///
+	/// Uses the implementation relationships if the indexer emitted them.
+	/// Otherwise, the trait is the symbol used at the trait's name in the
+	/// `impl Trait for Type` item, as the impl item's symbol only contains
+	/// the trait's display name.
+	fn find_trait_impls(&self, relationships: Vec<(String, String)>) -> HashMap<String, Vec<String>> {
+		let mut trait_items = HashMap::<_, Vec<_>>::new();
+		for (sym, def) in &self.definitions {
+			let Some(parent) = self.parents.get(sym) else {
+				continue
+			};
+			let is_trait = self.definitions.get(parent)
+				.map(|parent| parent.kind.map(|k| k.0) == Some(symbol_information::Kind::Trait))
+				.unwrap_or(false);
+			let Some(name) = def.name.as_deref().filter(|_| is_trait) else {
+				continue
+			};
+			trait_items.entry((parent.as_str(), name)).or_default().push(sym.clone());
+		}
+		let symbols_at = self.uses.iter()
+			.filter(|(sym, _uses)| !is_local_symbol(sym))
+			.flat_map(|(sym, uses)| uses.iter().map(move |u| ((u.span.file.clone(), u.span.region().0), sym.as_str())))
+			.collect::<HashMap<_, _>>();
+		let mut trait_impls = HashMap::<_, Vec<_>>::new();
+		for (sym, def) in &self.definitions {
+			let Ok(symbol) = parse_symbol(sym) else {
+				continue
+			};
+			let Some(name) = def.name.as_ref().filter(|_| impl_trait_name(&symbol).is_some()) else {
+				continue
+			};
+			// Traits from outside of the workspace have no items in the definitions
+			let trait_sym = self.sources.get(&def.span.file)
+				.and_then(|info| info.trait_impl(def.span.region()).map(|t| t.trait_pos))
+				.and_then(|pos| symbols_at.get(&(def.span.file.clone(), pos)).copied());
+			let items = trait_sym
+				.and_then(|trait_sym| trait_items.get(&(trait_sym, name.as_str())))
+				.cloned()
+				.unwrap_or_default();
+			trait_impls.insert(sym.clone(), items);
+		}
+		for (impl_sym, trait_sym) in relationships {
+			if !self.definitions.contains_key(&impl_sym) {
+				continue;
+			}
+			if !parse_symbol(&impl_sym).map(|s| is_assoc_item(&s)).unwrap_or(false) {
+				continue;
+			}
+			let items = trait_impls.entry(impl_sym).or_default();
+			if self.definitions.contains_key(&trait_sym) && !items.contains(&trait_sym) {
+				items.push(trait_sym);
+			}
+		}
+		trait_impls
+	}
+	/// Finds the entry points of the crates in the workspace
+	///
+	/// These are the crate root modules and `main` functions of the
+	/// workspace's cargo targets, as well as `#[test]` and `#[bench]` functions.
+	fn find_roots(&self) -> HashSet<String> {
+		let mut roots = HashSet::new();
+		let Some(root) = &self.root else {
+			warn!("Couldn't determine project root, not searching for entry points");
+			return roots;
+		};
+		let target_roots = self.metadata.iter()
+			.flat_map(|m| m.targets())
+			.filter_map(|t| {
+				let path = t.src_path.strip_prefix(root).ok()?;
+				Some((path.to_str()?, t))
+			})
+			.collect::<HashMap<_, _>>();
+		for (sym, def) in &self.definitions {
+			let Some(target) = target_roots.get(&*def.span.file) else {
+				continue
+			};
+			let kind = def.kind.map(|k| k.0);
+			let name = def.name.as_deref().unwrap_or_default();
+			let is_crate_root = kind == Some(symbol_information::Kind::Module) && name.is_empty();
+			let is_main = target.has_main() && kind == Some(symbol_information::Kind::Function)
+				&& name == "main"
+				&& parse_symbol(sym).map(|s| s.descriptors.len() == 1).unwrap_or(false);
+			if is_crate_root || is_main {
+				roots.insert(sym.clone());
+			}
+		}
+		for (sym, def) in &self.definitions {
+			let Some(info) = self.sources.get(&def.span.file) else {
+				continue
+			};
+			// Test functions are called by the test harness, and exported
+			// items like `#[no_mangle]` functions from outside of Rust
+			let pos = def.span.region().0;
+			if info.test_fns.contains(&pos) || info.exported.contains(&pos) {
+				roots.insert(sym.clone());
+			}
+			// Modules like `#[cfg(test)] mod tests { ... }` only exist for the test harness
+			if def.kind.map(|k| k.0) == Some(symbol_information::Kind::Module) && self.is_test_module(def, sym) {
+				roots.insert(sym.clone());
+			}
+		}
+		if !self.options.roots.is_empty() {
+			roots.extend(self.definitions.keys()
+				.filter(|sym| self.options.roots.is_symbol_match(sym))
+				.cloned());
+		}
+		if !self.options.public_api.is_empty() {
+			roots.extend(self.public_api().into_iter().map(|sym| sym.to_owned()));
+		}
+		roots
 	}
-	pub fn dump_index(&self) -> Result<(), StrErr> {
-		dump_index(&self.index)
+	/// Whether the module is test code, either itself or through its `mod name;` declaration
+	fn is_test_module(&self, def: &AbsDef, sym: &str) -> bool {
+		let is_test = |span: &Span| self.sources.get(&span.file)
+			.map(|info| info.is_test(span.region()))
+			.unwrap_or(false);
+		let is_test_decl = |u: &Use| is_test(&u.span) && self.sources.get(&u.span.file)
+			.map(|info| info.mod_decls.contains(&u.span.region().0))
+			.unwrap_or(false);
+		is_test(&def.span) || self.uses.get(sym).into_iter().flatten().any(is_test_decl)
 	}
-	pub fn get_unused_defs(&self) -> impl Iterator<Item=AbsDef> {
-		let mut used_defs = HashSet::new();
-		for doc in &self.index.documents {
-			for occ in &doc.occurrences {
-				if occ.symbol_roles & SymbolRole::Definition as i32 != 0 {
-					// Definitions we skip, as those don't count as use
+	/// Obtains the items reachable through the public interface
+	/// of the packages whose public API is considered used
+	///
+	/// This includes the items re-exported through `pub use`.
+	fn public_api(&self) -> HashSet<&str> {
+		let mut children = HashMap::<_, Vec<_>>::new();
+		for (sym, parent) in &self.parents {
+			children.entry(parent.as_str()).or_default().push(sym.as_str());
+		}
+		// The uses that name re-exported items, by the module they are in
+		let mut reexports = HashMap::<_, Vec<_>>::new();
+		for (sym, uses) in &self.uses {
+			for u in uses {
+				let Some(container) = &u.container else {
+					continue
+				};
+				let Some(info) = self.sources.get(&u.span.file) else {
+					continue
+				};
+				let pos = u.span.region().0;
+				if let Some(reexport) = info.imports.iter().find(|r| r.public && r.pos == pos) {
+					reexports.entry(container.as_str()).or_default().push((sym.as_str(), reexport.glob));
+				}
+			}
+		}
+		let kind_of = |sym: &str| self.definitions.get(sym)
+			.and_then(|def| def.kind)
+			.map(|kind| kind.0);
+		// Crate root modules of the selected packages
+		let mut queue = self.definitions.iter()
+			.filter(|(sym, def)| {
+				kind_of(sym) == Some(symbol_information::Kind::Module)
+					&& def.name.as_deref() == Some("")
+					&& package_name(sym).map(|name| self.options.has_public_api(&name)).unwrap_or(false)
+			})
+			.map(|(sym, _def)| (sym.as_str(), true))
+			.collect::<VecDeque<_>>();
+		let mut exported = HashSet::new();
+		let mut expanded = HashSet::new();
+		// Items are exported themselves if they are named, while glob re-exports
+		// only export the public items inside.
+		while let Some((sym, is_exported)) = queue.pop_front() {
+			if is_exported {
+				exported.insert(sym);
+			}
+			if !expanded.insert(sym) {
+				continue;
+			}
+			let parent_kind = kind_of(sym);
+			for &child in children.get(sym).into_iter().flatten() {
+				let Some(def) = self.definitions.get(child) else {
+					continue
+				};
+				// Trait items and the fields of enum variants don't have a visibility of their own
+				let is_public = def.visibility == Some(Visibility::Public)
+					|| matches!(parent_kind, Some(symbol_information::Kind::Trait | symbol_information::Kind::EnumMember));
+				if is_public {
+					queue.push_back((child, true));
+				}
+			}
+			for &(target, glob) in reexports.get(sym).into_iter().flatten() {
+				queue.push_back((target, !glob));
+			}
+		}
+		exported
+	}
+	/// Obtains the definitions reachable from the roots
+	///
+	/// Like rustc's `dead_code` lint, a definition only counts as used
+	/// if a use of it is inside a definition that is itself reachable.
+	/// Uses outside of any definition act as roots.
+	///
+	/// If test code is given, uses from test code and test functions are ignored.
+	fn reachable_defs(&self, test_code: Option<&TestCode>) -> HashSet<&str> {
+		self.reachability(test_code).into_keys().collect()
+	}
+	/// Obtains the reachable definitions, like `reachable_defs`, each mapped
+	/// to the definition it was first reached from, or None for the starting points
+	fn reachability(&self, test_code: Option<&TestCode>) -> HashMap<&str, Option<&str>> {
+		let is_test_use = |u: &Use| test_code.map(|t| t.is_test_use(u)).unwrap_or(false);
+		let impl_items = self.impl_items();
+		let mut edges = HashMap::<_, Vec<_>>::new();
+		let mut queue = VecDeque::new();
+		for (sym, uses) in &self.uses {
+			for u in uses.iter().filter(|u| u.is_actual() && !is_test_use(u)) {
+				// The implementing type and the trait named in the header of an impl
+				// are used by the impl's items, not by the module containing the impl
+				let header = self.sources.get(&u.span.file)
+					.and_then(|info| info.impl_header(u.span.region()).map(|i| i.region.0));
+				if let Some(header) = header {
+					for item in impl_items.get(&(&*u.span.file, header)).into_iter().flatten() {
+						edges.entry(*item).or_default().push(sym.as_str());
+					}
 					continue;
 				}
-				let symbol = parse_symbol(&occ.symbol).unwrap();
-				trace!("Adding used def {}", shorten_symbol(&symbol));
-				used_defs.insert(occ.symbol.clone());
+				match &u.container {
+					Some(container) => edges.entry(container.as_str()).or_default().push(sym.as_str()),
+					None => queue.push_back((sym.as_str(), None)),
+				}
+			}
+		}
+		// Anything inside of a reachable definition, like a struct's field,
+		// keeps the definition alive as well.
+		for (sym, parent) in &self.parents {
+			edges.entry(sym.as_str()).or_default().push(parent.as_str());
+		}
+		// Record implementations of traits as used if the trait's item is used,
+		// or if the trait is from outside of the workspace.
+		// The trait's item is used as well if one of its implementations is.
+		for (sym, trait_items) in &self.trait_impls {
+			if trait_items.is_empty() {
+				queue.push_back((sym.as_str(), None));
+			}
+			for trait_item in trait_items {
+				edges.entry(trait_item.as_str()).or_default().push(sym.as_str());
+				edges.entry(sym.as_str()).or_default().push(trait_item.as_str());
+			}
+		}
+		queue.extend(self.roots.iter()
+			.filter(|sym| !test_code.map(|t| t.is_test_sym(sym)).unwrap_or(false))
+			.map(|sym| (sym.as_str(), None)));
+		let mut reachable = HashMap::new();
+		while let Some((sym, from)) = queue.pop_front() {
+			if reachable.contains_key(sym) {
+				continue;
+			}
+			reachable.insert(sym, from);
+			trace!("Reachable def {sym}");
+			queue.extend(edges.get(sym).into_iter().flatten()
+				.map(|next| (*next, Some(sym))));
+		}
+		reachable
+	}
+	/// Obtains the definitions inside of impls, by the file and start of the innermost impl
+	fn impl_items(&self) -> HashMap<(&str, source::Position), Vec<&str>> {
+		let mut impl_items = HashMap::<_, Vec<_>>::new();
+		for (sym, def) in &self.definitions {
+			if is_local_symbol(sym) {
+				continue;
+			}
+			let Some(info) = self.sources.get(&def.span.file) else {
+				continue
+			};
+			if let Some(i) = info.impl_containing(def.span.region()) {
+				impl_items.entry((&*def.span.file, i.region.0)).or_default().push(sym.as_str());
 			}
 		}
-		let mut unused_defs = self.definitions.iter()
+		impl_items
+	}
+	/// Obtains the unused definitions, including those where it's allowed by an attribute
+	fn unsuppressed_unused_defs(&self) -> Vec<(&str, &AbsDef)> {
+		let mut used_defs = self.uses.iter()
+			.filter(|(_sym, uses)| uses.iter().any(Use::is_actual))
+			.map(|(sym, _uses)| sym.as_str())
+			.collect::<HashSet<_>>();
+		// Trait items are used if one of their implementations is
+		let used_trait_items = self.trait_impls.iter()
+			.filter(|(sym, _trait_items)| used_defs.contains(sym.as_str()))
+			.flat_map(|(_sym, trait_items)| trait_items.iter().map(|item| item.as_str()))
+			.collect::<Vec<_>>();
+		used_defs.extend(used_trait_items);
+		let reachable_defs = if self.options.recurse {
+			self.reachable_defs(None)
+		} else {
+			HashSet::new()
+		};
+		self.definitions.iter()
 			.filter(|(sym, def)| {
-				if used_defs.get(sym.as_str()).is_some() {
+				if self.roots.contains(sym.as_str()) {
+					return false;
+				}
+				if !self.options.recurse && self.trait_impls.contains_key(sym.as_str()) {
+					// Don't do any recursion
+					return false;
+				}
+				if self.options.recurse && !is_local_symbol(sym) {
+					if reachable_defs.contains(sym.as_str()) {
+						return false;
+					}
+				} else if used_defs.contains(sym.as_str()) {
 					return false;
 				}
 				// Anything starting with _ can be unused without warning.
-				if def.name.as_ref().map(|name| name.starts_with("_")).unwrap_or_default() {
+				if def.name.as_ref().map(|name| name.starts_with('_')).unwrap_or_default() {
+					return false;
+				}
+				// Self may be unused without warning.
+				if def.kind.map(|kind| kind.0) == Some(symbol_information::Kind::SelfParameter) {
 					return false;
 				}
 				true
 			})
-			.map(|(_sym, def)| def.clone())
+			.map(|(sym, def)| (sym.as_str(), def))
+			.collect::<Vec<_>>()
+	}
+	fn is_allowed(&self, def: &AbsDef) -> bool {
+		self.sources.is_allowed(&def.span.file, def.span.region())
+	}
+	/// Obtains the `#[expect(dead_code)]` attributes which don't apply to any of the unused definitions
+	fn unfulfilled_expectations(&self, unused_defs: &[(&str, &AbsDef)]) -> Vec<Span> {
+		let mut unfulfilled = Vec::new();
+		for doc in &self.index.documents {
+			let Some(info) = self.sources.get(&doc.relative_path) else {
+				continue
+			};
+			let file: Arc<str> = Arc::from(doc.relative_path.as_str());
+			for expectation in &info.expectations {
+				let fulfilled = unused_defs.iter()
+					.flat_map(|(_sym, def)| self.sources.locations(&def.span.file, def.span.region()))
+					.any(|(def_file, region)| def_file == &*file && source::contains(expectation.region, region));
+				if !fulfilled {
+					let (line, col) = expectation.attr;
+					unfulfilled.push(Span {
+						file: file.clone(),
+						start_line: line as u32,
+						start_col: col as u32,
+						end_line: line as u32,
+						end_col: col as u32,
+					});
+				}
+			}
+		}
+		unfulfilled.sort();
+		unfulfilled
+	}
+	/// Finds the definition of a symbol, or the one at a `path:line:col` location
+	pub fn find_definition(&self, query: &str) -> Option<&str> {
+		if let Some((sym, _def)) = self.definitions.get_key_value(query) {
+			return Some(sym);
+		}
+		let mut parts = query.rsplitn(3, ':');
+		let col = parts.next()?.parse::<u32>().ok()?;
+		let line = parts.next()?.parse::<u32>().ok()?;
+		let file = parts.next()?;
+		self.definitions.iter()
+			.filter(|(_sym, def)| &*def.span.file == file)
+			.filter(|(_sym, def)| def.span.start() <= (line, col) && def.span.end() >= (line, col))
+			// Module definitions span their entire file, prefer the innermost definition
+			.max_by(|(_, a), (_, b)| a.span.start().cmp(&b.span.start()).then(b.span.end().cmp(&a.span.end())))
+			.map(|(sym, _def)| sym.as_str())
+	}
+	/// Prints information about a definition and its uses
+	pub fn explain(&self, w: &mut dyn Write, sym: &str) -> Result<(), Error> {
+		let Some(def) = self.definitions.get(sym) else {
+			return Err(Error::InvalidArgument(format!("no definition found for '{sym}'")));
+		};
+		let kind = def.kind.map(|k| format!("{k:?}")).unwrap_or_else(|| "<unknown>".to_owned());
+		writeln!(w, "symbol: {sym}")?;
+		writeln!(w, "definition: {} {} '{}'", def.span.display_str(), kind, def.name.as_deref().unwrap_or_default())?;
+		let finding = self.get_findings().into_iter()
+			.find(|finding| finding.symbol.as_deref() == Some(sym));
+		if let Some(finding) = finding {
+			writeln!(w, "status: reported: {}", finding.message())?;
+		} else if let Some(reason) = self.root_reason(sym, def) {
+			writeln!(w, "status: used, root: {reason}")?;
+		} else {
+			writeln!(w, "status: not reported")?;
+		}
+		let filters = self.filters(sym, def);
+		if filters.is_empty() {
+			writeln!(w, "filters: none")?;
+		} else {
+			writeln!(w, "filters:")?;
+			for filter in filters {
+				writeln!(w, "  {filter}")?;
+			}
+		}
+		let uses = self.uses.get(sym).map(|uses| &uses[..]).unwrap_or_default();
+		writeln!(w, "uses: {}", uses.len())?;
+		for u in uses {
+			let container = u.container.as_deref().unwrap_or("<top level>");
+			writeln!(w, "  {} in {} [{}]", u.span.display_str(), container, u.roles.names().join(", "))?;
+		}
+		if self.options.recurse {
+			self.explain_reachability(w, sym)?;
+		}
+		Ok(())
+	}
+	/// Prints the chain of definitions through which the definition is reachable from a root
+	fn explain_reachability(&self, w: &mut dyn Write, sym: &str) -> Result<(), Error> {
+		let reachability = self.reachability(None);
+		if !reachability.contains_key(sym) {
+			writeln!(w, "reachability: not reachable from any root")?;
+			return Ok(());
+		}
+		let mut chain = vec![sym];
+		while let Some(Some(from)) = reachability.get(chain[chain.len() - 1]) {
+			chain.push(from);
+		}
+		chain.reverse();
+		writeln!(w, "reachability:")?;
+		let location = |sym: &str| self.definitions.get(sym)
+			.map(|def| def.span.display_str())
+			.unwrap_or_default();
+		let start = chain[0];
+		let start_reason = match (self.trait_impls.get(start), self.definitions.get(start)) {
+			(Some(trait_items), _) if trait_items.is_empty() => "implements a trait from outside of the workspace",
+			(_, Some(def)) if self.roots.contains(start) => self.root_reason(start, def).unwrap_or("root"),
+			_ => "used outside of any definition",
+		};
+		writeln!(w, "  {} {} ({})", location(start), start, start_reason)?;
+		for pair in chain.windows(2) {
+			let (prev, cur) = (pair[0], pair[1]);
+			let relation = if self.parents.get(prev).map(|p| p.as_str()) == Some(cur) {
+				"contains the above"
+			} else if self.trait_impls.get(cur).map(|items| items.iter().any(|i| i == prev)).unwrap_or(false) {
+				"implements the above"
+			} else if self.trait_impls.get(prev).map(|items| items.iter().any(|i| i == cur)).unwrap_or(false) {
+				"implemented by the above"
+			} else {
+				"used by the above"
+			};
+			writeln!(w, "  -> {} {} ({})", location(cur), cur, relation)?;
+		}
+		Ok(())
+	}
+	/// Obtains why the definition is a root, if it is one
+	fn root_reason(&self, sym: &str, def: &AbsDef) -> Option<&'static str> {
+		if !self.roots.contains(sym) {
+			return None;
+		}
+		let pos = def.span.region().0;
+		if let Some(info) = self.sources.get(&def.span.file) {
+			if info.test_fns.contains(&pos) {
+				return Some("test function");
+			}
+			if info.exported.contains(&pos) {
+				return Some("used from outside of Rust");
+			}
+		}
+		if self.options.roots.is_symbol_match(sym) {
+			return Some("matches a root pattern");
+		}
+		if !self.options.public_api.is_empty() && self.public_api().contains(sym) {
+			return Some("part of the public API");
+		}
+		Some("entry point of a cargo target")
+	}
+	/// Obtains descriptions of the rules that exclude the definition from being reported
+	fn filters(&self, sym: &str, def: &AbsDef) -> Vec<String> {
+		let mut filters = Vec::new();
+		if is_local_symbol(sym) {
+			filters.push("local: only checked whether it is used at all, even with --recurse".to_owned());
+		}
+		if def.name.as_ref().map(|name| name.starts_with('_')).unwrap_or_default() {
+			filters.push("name starts with `_`".to_owned());
+		}
+		if def.kind.map(|kind| kind.0) == Some(symbol_information::Kind::SelfParameter) {
+			filters.push("self parameter".to_owned());
+		}
+		match self.trait_impls.get(sym) {
+			Some(trait_items) if trait_items.is_empty() =>
+				filters.push("implements a trait from outside of the workspace".to_owned()),
+			Some(_) if !self.options.recurse =>
+				filters.push("item of a trait impl, only checked with --recurse".to_owned()),
+			Some(_) => filters.push("item of a trait impl, used if the trait's item is".to_owned()),
+			None => (),
+		}
+		if !self.options.recurse {
+			let used_impl = self.trait_impls.iter()
+				.filter(|(_impl_sym, trait_items)| trait_items.iter().any(|item| item == sym))
+				.find(|(impl_sym, _trait_items)| self.actual_uses(impl_sym).next().is_some());
+			if let Some((impl_sym, _trait_items)) = used_impl {
+				filters.push(format!("item of a trait, used through its implementation {impl_sym}"));
+			}
+		}
+		if self.is_allowed(def) {
+			filters.push("#[allow(dead_code)] or #[expect(dead_code)]".to_owned());
+		}
+		if let Some(info) = self.sources.get(&def.span.file) {
+			let suppression = info.suppressions.iter()
+				.find(|s| s.region.map(|r| source::contains(r, def.span.region())).unwrap_or(false));
+			if let Some(suppression) = suppression {
+				let (line, col) = suppression.comment;
+				filters.push(format!("warnalyzer: ignore comment at {}:{line}:{col}", def.span.file));
+			}
+		}
+		if self.options.ignore_paths.is_match(&def.span.file) {
+			filters.push("file matches ignore-paths".to_owned());
+		}
+		if self.options.ignore_symbols.is_symbol_match(sym) {
+			filters.push("symbol matches ignore-symbols".to_owned());
+		}
+		if !self.options.reports_package(package_name(sym).as_deref()) {
+			filters.push("package not selected by --package or --exclude".to_owned());
+		}
+		filters
+	}
+	/// Obtains the fields and statics that are written to, but never read,
+	/// including those where it's allowed by an attribute
+	fn write_only_defs(&self) -> Vec<(&str, &AbsDef)> {
+		self.definitions.iter()
+			.filter(|(sym, def)| {
+				let kind = def.kind.map(|k| k.0);
+				let is_field_or_static = matches!(kind,
+					Some(symbol_information::Kind::Field | symbol_information::Kind::StaticVariable));
+				if !is_field_or_static || is_local_symbol(sym) || self.roots.contains(sym.as_str()) {
+					return false;
+				}
+				// Unused definitions are reported already
+				let mut uses = self.actual_uses(sym).peekable();
+				uses.peek().is_some() && uses.all(|u| u.roles.is_write_access() && !u.roles.is_read_access())
+			})
+			.map(|(sym, def)| (sym.as_str(), def))
+			.collect()
+	}
+	/// Obtains the enum variants that are only used in patterns, but never constructed,
+	/// including those where it's allowed by an attribute
+	fn never_constructed_defs(&self) -> Vec<(&str, &AbsDef)> {
+		self.definitions.iter()
+			.filter(|(sym, def)| {
+				if def.kind.map(|k| k.0) != Some(symbol_information::Kind::EnumMember) {
+					return false;
+				}
+				if is_local_symbol(sym) || self.roots.contains(sym.as_str()) {
+					return false;
+				}
+				// Unused definitions are reported already
+				let mut uses = self.actual_uses(sym).peekable();
+				uses.peek().is_some() && uses.all(|u| u.roles.is_read_access() && !u.roles.is_write_access())
+			})
+			.map(|(sym, def)| (sym.as_str(), def))
+			.collect()
+	}
+	/// Obtains the imports of definitions that have no actual uses
+	fn unused_imports(&self) -> Vec<(&str, &AbsDef, &Use)> {
+		let mut unused_imports = Vec::new();
+		for (sym, uses) in &self.uses {
+			let Some(def) = self.definitions.get(sym) else {
+				continue
+			};
+			if is_local_symbol(sym) || self.roots.contains(sym) || uses.iter().any(Use::is_actual) {
+				continue;
+			}
+			for u in uses {
+				if u.roles.is_import() && !self.sources.is_import_allowed(&u.span.file, u.span.region()) {
+					unused_imports.push((sym.as_str(), def, u));
+				}
+			}
+		}
+		unused_imports
+	}
+	/// Obtains the uses of the symbol which aren't only imports
+	fn actual_uses(&self, sym: &str) -> impl Iterator<Item=&Use> {
+		self.uses.get(sym).into_iter()
+			.flatten()
+			.filter(|u| u.is_actual())
+	}
+	/// Obtains the definitions that are only used by test code
+	fn test_only_defs(&self) -> Vec<(&str, &AbsDef)> {
+		let test_code = TestCode::new(self);
+		let reachable = if self.options.recurse {
+			let reachable = self.reachable_defs(None);
+			let reachable_without_tests = self.reachable_defs(Some(&test_code));
+			Some((reachable, reachable_without_tests))
+		} else {
+			None
+		};
+		self.definitions.iter()
+			.filter(|(sym, _def)| !is_local_symbol(sym) && !self.roots.contains(sym.as_str()))
+			.filter(|(sym, _def)| match &reachable {
+				Some((reachable, without_tests)) => {
+					reachable.contains(sym.as_str()) && !without_tests.contains(sym.as_str())
+				},
+				None => {
+					let mut uses = self.actual_uses(sym).peekable();
+					uses.peek().is_some() && uses.all(|u| test_code.is_test_use(u))
+				},
+			})
+			.filter(|(sym, _def)| !test_code.is_test_sym(sym))
+			.filter(|(_sym, def)| !self.is_allowed(def))
+			.map(|(sym, def)| (sym.as_str(), def))
+			.collect()
+	}
+	/// Obtains an identifier of the crate each document belongs to
+	///
+	/// The crate of a module's file is the one whose root declares the module,
+	/// possibly through the `mod name;` declarations of other modules.
+	fn crates_by_file(&self) -> HashMap<&str, String> {
+		let Some(root) = &self.root else {
+			return HashMap::new();
+		};
+		let target_roots = self.metadata.iter()
+			.flat_map(|m| m.members())
+			.flat_map(|p| p.targets.iter().map(move |t| (p, t)))
+			.filter_map(|(p, t)| {
+				let path = t.src_path.strip_prefix(root).ok()?;
+				Some((path.to_str()?, format!("{} {}", p.name, path.display())))
+			})
+			.collect::<HashMap<_, _>>();
+		let mut crates = HashMap::new();
+		for doc in &self.index.documents {
+			let crate_root = self.sources.root_file(&doc.relative_path);
+			if let Some(krate) = target_roots.get(crate_root) {
+				crates.insert(doc.relative_path.as_str(), krate.clone());
+			}
+		}
+		crates
+	}
+	/// Obtains the definitions whose visibility is larger than what their uses need
+	fn overly_visible_defs(&self) -> Vec<(&str, &AbsDef, Reason)> {
+		let crates = self.crates_by_file();
+		let mut overly_visible = Vec::new();
+		for (sym, def) in &self.definitions {
+			if is_local_symbol(sym) || self.roots.contains(sym.as_str()) {
+				continue;
+			}
+			// Unused definitions are reported already
+			if self.actual_uses(sym).next().is_none() {
+				continue;
+			}
+			// Imports need the visibility as well, like of a trait to call its methods
+			let uses = self.uses.get(sym).map(|uses| &uses[..]).unwrap_or_default();
+			// Enum variants and trait items have the visibility of their enum or trait
+			let kind = def.kind.map(|k| k.0);
+			let parent_kind = self.parents.get(sym)
+				.and_then(|parent| self.definitions.get(parent))
+				.and_then(|parent| parent.kind)
+				.map(|k| k.0);
+			if kind == Some(symbol_information::Kind::EnumMember) || parent_kind == Some(symbol_information::Kind::Trait) {
+				continue;
+			}
+			match def.visibility {
+				Some(Visibility::Public) => {
+					let Some(def_crate) = crates.get(&*def.span.file) else {
+						continue
+					};
+					let outside_crate = |u: &Use| crates.get(&*u.span.file) != Some(def_crate);
+					if !uses.iter().any(outside_crate) && !self.in_exposed_signature(sym, &outside_crate) {
+						overly_visible.push((sym.as_str(), def, Reason::CouldBeCrateVisible));
+					}
+				},
+				Some(Visibility::Crate) => {
+					let Some(module) = parent_module_path(sym) else {
+						continue
+					};
+					// Private items are visible in the submodules as well
+					let outside_module = |u: &Use| !u.container.as_deref()
+						.and_then(module_path)
+						.map(|m| m.starts_with(&module))
+						.unwrap_or(false);
+					if !uses.iter().any(outside_module) && !self.in_exposed_signature(sym, &outside_module) {
+						overly_visible.push((sym.as_str(), def, Reason::CouldBePrivate));
+					}
+				},
+				_ => (),
+			}
+		}
+		overly_visible
+	}
+	/// Whether the definition is named in the signature of an item used from outside,
+	/// so reducing its visibility would put a private type into a public interface
+	fn in_exposed_signature(&self, sym: &str, is_outside: &dyn Fn(&Use) -> bool) -> bool {
+		self.in_exposed_signature_visited(sym, is_outside, &mut HashSet::new())
+	}
+	fn in_exposed_signature_visited<'a>(&'a self, sym: &str, is_outside: &dyn Fn(&Use) -> bool, visited: &mut HashSet<&'a str>) -> bool {
+		let in_body = |u: &Use| self.sources.get(&u.span.file)
+			.map(|info| info.is_in_body(u.span.region()))
+			.unwrap_or(false);
+		self.uses.get(sym).into_iter()
+			.flatten()
+			.filter(|u| !in_body(u))
+			.filter_map(|u| u.container.as_deref())
+			.any(|container| self.is_exposed(container, is_outside, visited))
+	}
+	/// Whether the definition, or the type it is a field or method of, is used from outside,
+	/// either directly or by being named in the signature of such an item
+	fn is_exposed<'a>(&'a self, sym: &'a str, is_outside: &dyn Fn(&Use) -> bool, visited: &mut HashSet<&'a str>) -> bool {
+		let mut cur = Some(sym);
+		while let Some(sym) = cur {
+			if !visited.insert(sym) {
+				return false;
+			}
+			let Some(def) = self.definitions.get(sym) else {
+				return false
+			};
+			if def.kind.map(|k| k.0) == Some(symbol_information::Kind::Module) {
+				return false;
+			}
+			let is_public_root = def.visibility == Some(Visibility::Public) && self.roots.contains(sym);
+			if is_public_root || self.uses.get(sym).into_iter().flatten().any(is_outside) {
+				return true;
+			}
+			if self.in_exposed_signature_visited(sym, is_outside, visited) {
+				return true;
+			}
+			cur = self.parents.get(sym).map(|parent| parent.as_str());
+		}
+		false
+	}
+	fn finding(&self, sym: &str, def: &AbsDef) -> Finding {
+		let reason = if self.options.recurse && !is_local_symbol(sym) && self.actual_uses(sym).next().is_some() {
+			Reason::Unreachable
+		} else {
+			Reason::Unused
+		};
+		Finding {
+			span: def.span.clone(),
+			name: def.name.clone().unwrap_or_default(),
+			kind: def.kind.map(|k| format!("{k:?}")).unwrap_or_else(|| "<unknown>".to_owned()),
+			krate: package_name(sym),
+			visibility: def.visibility,
+			reason,
+			severity: Severity::Warning,
+			symbol: Some(sym.to_owned()),
+			// Local symbols are renumbered whenever their file changes
+			container: self.parents.get(sym).filter(|_| is_local_symbol(sym)).cloned(),
+		}
+	}
+}
+
+impl Backend for AnalysisDb {
+	fn get_findings(&self) -> Vec<Finding> {
+		let unused_defs = self.unsuppressed_unused_defs();
+		let write_only_defs = self.write_only_defs();
+		let never_constructed_defs = self.never_constructed_defs();
+		let mut findings = unused_defs.iter()
+			.filter(|(_sym, def)| !self.is_allowed(def))
+			.map(|(sym, def)| self.finding(sym, def))
 			.collect::<Vec<_>>();
-		unused_defs.sort();
-		unused_defs.into_iter()
+		findings.extend(write_only_defs.iter()
+			.filter(|(_sym, def)| !self.is_allowed(def))
+			.map(|(sym, def)| Finding {
+				reason: Reason::WriteOnly,
+				..self.finding(sym, def)
+			}));
+		findings.extend(never_constructed_defs.iter()
+			.filter(|(_sym, def)| !self.is_allowed(def))
+			.map(|(sym, def)| Finding {
+				reason: Reason::NeverConstructed,
+				..self.finding(sym, def)
+			}));
+		// The package of a file is the one of the definitions in it
+		let file_packages = self.definitions.iter()
+			.filter(|(sym, _def)| !is_local_symbol(sym))
+			.filter_map(|(sym, def)| Some((def.span.file.clone(), package_name(sym)?)))
+			.collect::<HashMap<_, _>>();
+		// rustc's dead_code lint covers fields that are never read as well
+		let dead_defs = [&unused_defs[..], &write_only_defs[..], &never_constructed_defs[..]].concat();
+		findings.extend(self.unfulfilled_expectations(&dead_defs).into_iter()
+			.map(|span| Finding {
+				krate: file_packages.get(&span.file).cloned(),
+				span,
+				name: String::new(),
+				kind: String::new(),
+				visibility: None,
+				reason: Reason::UnfulfilledExpectation,
+				severity: Severity::Warning,
+				symbol: None,
+				container: None,
+			}));
+		findings.extend(self.unused_imports().into_iter()
+			.map(|(sym, def, u)| Finding {
+				span: u.span.clone(),
+				krate: file_packages.get(&u.span.file).cloned(),
+				reason: Reason::UnusedImport,
+				..self.finding(sym, def)
+			}));
+		if self.options.test_only {
+			findings.extend(self.test_only_defs().into_iter()
+				.map(|(sym, def)| Finding {
+					reason: Reason::TestOnly,
+					..self.finding(sym, def)
+				}));
+		}
+		if self.options.check_visibility {
+			findings.extend(self.overly_visible_defs().into_iter()
+				.map(|(sym, def, reason)| Finding {
+					reason,
+					..self.finding(sym, def)
+				}));
+		}
+		for doc in &self.index.documents {
+			let Some(info) = self.sources.get(&doc.relative_path) else {
+				continue
+			};
+			let file: Arc<str> = Arc::from(doc.relative_path.as_str());
+			let stale = source::suppress(&info, &file, &mut findings);
+			findings.extend(stale.into_iter()
+				.map(|(line, col)| Finding {
+					krate: file_packages.get(&file).cloned(),
+					span: Span {
+						file: file.clone(),
+						start_line: line as u32,
+						start_col: col as u32,
+						end_line: line as u32,
+						end_col: col as u32,
+					},
+					name: String::new(),
+					kind: String::new(),
+					visibility: None,
+					reason: Reason::StaleSuppression,
+					severity: Severity::Warning,
+					symbol: None,
+					container: None,
+				}));
+		}
+		self.options.apply(&mut findings);
+		findings.sort();
+		findings
+	}
+	fn skipped(&self) -> &Skipped {
+		&self.skipped
 	}
 }
 
-pub fn run_scip(dir: &Path, output_file: &Path) -> Result<(), StrErr> {
+pub fn run_scip(dir: &Path, output_file: &Path, extra_args: &[String]) -> Result<(), Error> {
 	let mut process = Command::new("rust-analyzer")
 		.arg("scip")
 		.arg(dir)
 		.arg("--output")
 		.arg(output_file)
+		.args(extra_args)
 		.spawn()?;
 	let result = process.wait()?;
 	if !result.success() {
-		return Err(StrErr(format!("rust-analyzer command failed")));
+		return Err(Error::Command {
+			command: "rust-analyzer scip".to_owned(),
+			message: result.to_string(),
+		});
 	}
 	Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,639 @@
+use crate::{finding::Finding, Error};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chashmap::CHashMap;
+use proc_macro2::{LineColumn, Span};
+use syn::{Attribute, Expr, ExprAssign, ExprAssignOp, ExprStruct, Field, Ident, ImplItem, Item, ItemFn, ItemImpl, ItemMod, ItemStatic, ItemUse, ImplItemMethod, Member, Meta, NestedMeta, Pat, Signature, TraitItem, TraitItemMethod, UseTree, Variant};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+/// A 1-based line and column pair
+pub type Position = (usize, usize);
+
+/// The start and end positions of some piece of code
+pub type Region = (Position, Position);
+
+fn lc(v :LineColumn) -> Position {
+	// Columns are 0-based for some reason...
+	// https://github.com/rust-lang/rust/issues/54725
+	(v.line, v.column + 1)
+}
+
+fn region(sp :Span) -> Region {
+	(lc(sp.start()), lc(sp.end()))
+}
+
+/// Whether `outer` fully contains `inner`
+pub fn contains(outer :Region, inner :Region) -> bool {
+	outer.0 <= inner.0 && outer.1 >= inner.1
+}
+
+/// An `#[expect(dead_code)]` attribute
+#[derive(Clone, Copy, Debug)]
+pub struct Expectation {
+	/// Position of the attribute itself
+	pub attr :Position,
+	/// Region of the item the attribute is applied to
+	pub region :Region,
+}
+
+/// A `// warnalyzer: ignore` comment
+#[derive(Clone, Copy, Debug)]
+pub struct Suppression {
+	/// Position of the comment
+	pub comment :Position,
+	/// Region of the item the comment applies to, if there is one
+	pub region :Option<Region>,
+}
+
+/// An item imported by a `use`
+#[derive(Clone, Copy, Debug)]
+pub struct Import {
+	/// Position of the last path segment naming the item,
+	/// or of the segment before the `*` for glob imports
+	pub pos :Position,
+	/// Whether all public items inside the named item are imported
+	pub glob :bool,
+	/// Whether the import is a `pub use` re-export
+	pub public :bool,
+}
+
+/// An `impl Trait for Type` item
+#[derive(Clone, Copy, Debug)]
+pub struct TraitImpl {
+	/// Region of the impl item
+	pub region :Region,
+	/// Position of the last path segment naming the trait
+	pub trait_pos :Position,
+}
+
+/// An `impl` item
+#[derive(Clone, Copy, Debug)]
+pub struct Impl {
+	/// Region of the impl item
+	pub region :Region,
+	/// Region of the impl item up to its body, with the implementing type and the trait
+	pub header :Region,
+}
+
+/// Information obtained by parsing the source code of a file
+#[derive(Default, Debug)]
+pub struct SourceInfo {
+	/// Positions of the names of `#[test]` and `#[bench]` functions
+	pub test_fns :Vec<Position>,
+	/// Regions of items with `#[allow(dead_code)]` or `#[allow(unused)]`
+	pub allowed :Vec<Region>,
+	/// Items with `#[expect(dead_code)]` or `#[expect(unused)]`
+	pub expectations :Vec<Expectation>,
+	/// Regions of items where the `unused_imports` lint is allowed or expected
+	pub allowed_imports :Vec<Region>,
+	/// Items imported by `use`
+	pub imports :Vec<Import>,
+	/// Regions of `#[cfg(test)]` items and of `#[test]` and `#[bench]` functions
+	pub test_regions :Vec<Region>,
+	/// Positions of the names of modules declared via `mod name;`
+	pub mod_decls :Vec<Position>,
+	/// Positions of names that are only written to, like fields in struct
+	/// expressions or the left hand side of assignments
+	pub writes :Vec<Position>,
+	/// Positions of the last path segments of patterns, like `Some` in `Some(v) => ...`
+	pub pattern_paths :Vec<Position>,
+	/// Regions of `#[derive(...)]` attributes
+	pub derives :Vec<Region>,
+	/// Positions of the path segments naming the derive macros in `#[derive(...)]` attributes
+	pub derive_paths :Vec<Position>,
+	/// Positions of the names of items that can be used from outside of Rust,
+	/// like `#[no_mangle]` or `extern "C"` functions
+	pub exported :Vec<Position>,
+	/// Comments suppressing the findings of items
+	pub suppressions :Vec<Suppression>,
+	/// Impls of traits
+	pub trait_impls :Vec<TraitImpl>,
+	/// All impls, inherent ones and those of traits
+	pub impls :Vec<Impl>,
+	/// Regions of the bodies of functions and methods
+	pub bodies :Vec<Region>,
+}
+
+impl SourceInfo {
+	/// Whether the region is inside of test code
+	pub fn is_test(&self, needle :Region) -> bool {
+		self.test_regions.iter().any(|r| contains(*r, needle))
+	}
+	/// Whether the region is inside an item where dead code is allowed or expected
+	pub fn is_allowed(&self, needle :Region) -> bool {
+		self.allowed.iter().any(|r| contains(*r, needle)) ||
+			self.expectations.iter().any(|e| contains(e.region, needle))
+	}
+	/// Whether the region is inside the body of a function, instead of its signature
+	pub fn is_in_body(&self, needle :Region) -> bool {
+		self.bodies.iter().any(|r| contains(*r, needle))
+	}
+	/// Finds the innermost trait impl containing the region
+	pub fn trait_impl(&self, needle :Region) -> Option<&TraitImpl> {
+		self.trait_impls.iter()
+			.filter(|t| contains(t.region, needle))
+			.max_by_key(|t| t.region.0)
+	}
+	/// Finds the innermost impl containing the region
+	pub fn impl_containing(&self, needle :Region) -> Option<&Impl> {
+		self.impls.iter()
+			.filter(|i| contains(i.region, needle))
+			.max_by_key(|i| i.region.0)
+	}
+	/// Finds the impl whose header contains the region
+	pub fn impl_header(&self, needle :Region) -> Option<&Impl> {
+		self.impls.iter()
+			.find(|i| contains(i.header, needle))
+	}
+	/// Whether the region is inside an item where unused imports are allowed or expected
+	pub fn is_import_allowed(&self, needle :Region) -> bool {
+		self.allowed_imports.iter().any(|r| contains(*r, needle))
+	}
+}
+
+/// The text of comments suppressing the findings of items, optionally followed by a reason
+const SUPPRESSION_MARKER :&str = "warnalyzer: ignore";
+
+/// Finds the suppression comments, together with the line of the item they apply to
+///
+/// Comments on a line of their own apply to the item starting on the next line
+/// that is neither empty nor a comment, others to the item starting on their line.
+fn suppression_comments(file :&str) -> Vec<(Position, usize)> {
+	let lines = file.lines().collect::<Vec<_>>();
+	let is_code = |line :&str| {
+		let line = line.trim();
+		// Doc comments are attributes of the item
+		!line.is_empty() && (!line.starts_with("//") || line.starts_with("///"))
+	};
+	let mut comments = Vec::new();
+	for (i, line) in lines.iter().enumerate() {
+		// An odd number of quotes before the slashes means they are inside a string
+		let Some((col, _)) = line.match_indices("//")
+			.filter(|(col, _)| line[..*col].matches('"').count() % 2 == 0)
+			.find(|(col, _)| line[col + 2..].trim_start().starts_with(SUPPRESSION_MARKER)) else {
+			continue
+		};
+		let target = if line[..col].trim().is_empty() {
+			match (i + 1..lines.len()).find(|j| is_code(lines[*j])) {
+				Some(j) => j + 1,
+				None => 0,
+			}
+		} else {
+			i + 1
+		};
+		comments.push(((i + 1, line[..col].chars().count() + 1), target));
+	}
+	comments
+}
+
+/// Removes the findings in the file which are suppressed by comments
+///
+/// Returns the positions of the comments that don't suppress any finding.
+pub fn suppress(info :&SourceInfo, file :&str, findings :&mut Vec<Finding>) -> Vec<Position> {
+	let mut used = vec![false; info.suppressions.len()];
+	findings.retain(|f| {
+		if &*f.span.file != file {
+			return true;
+		}
+		let mut suppressed = false;
+		for (suppression, used) in info.suppressions.iter().zip(used.iter_mut()) {
+			if suppression.region.map(|r| contains(r, f.span.region())).unwrap_or(false) {
+				*used = true;
+				suppressed = true;
+			}
+		}
+		!suppressed
+	});
+	info.suppressions.iter().zip(used)
+		.filter(|(_suppression, used)| !used)
+		.map(|(suppression, _used)| suppression.comment)
+		.collect()
+}
+
+fn is_test_attr(attr :&Attribute) -> bool {
+	// Also covers attributes like #[tokio::test]
+	attr.path.segments.last()
+		.map(|seg| seg.ident == "test" || seg.ident == "bench")
+		.unwrap_or(false)
+}
+
+/// Whether the attribute exports the item under a fixed symbol name
+fn is_export_attr(attr :&Attribute) -> bool {
+	attr.path.is_ident("no_mangle") || attr.path.is_ident("export_name")
+}
+
+/// Whether the function uses an ABI other than Rust's, like `extern "C" fn`
+fn has_foreign_abi(sig :&Signature) -> bool {
+	// `extern fn` without a name uses the C ABI
+	sig.abi.as_ref()
+		.map(|abi| abi.name.as_ref().map(|name| name.value() != "Rust").unwrap_or(true))
+		.unwrap_or(false)
+}
+
+/// Whether the cfg predicate only holds when compiling tests
+fn requires_test(meta :&NestedMeta) -> bool {
+	match meta {
+		NestedMeta::Meta(Meta::Path(p)) => p.is_ident("test"),
+		NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("all") => {
+			list.nested.iter().any(requires_test)
+		},
+		_ => false,
+	}
+}
+
+fn is_cfg_test_attr(attr :&Attribute) -> bool {
+	let Ok(Meta::List(list)) = attr.parse_meta() else {
+		return false
+	};
+	list.path.is_ident("cfg") && list.nested.iter().any(requires_test)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LintLevel {
+	Allow,
+	Expect,
+}
+
+/// The lints whose level applies to dead code
+const DEAD_CODE_LINTS :&[&str] = &["dead_code", "unused"];
+
+/// The lints whose level applies to unused imports
+const UNUSED_IMPORTS_LINTS :&[&str] = &["unused_imports", "unused"];
+
+/// The level the attribute sets any of the lints to, if any
+fn lint_level(meta :&Meta, lints :&[&str]) -> Option<LintLevel> {
+	let Meta::List(list) = meta else {
+		return None
+	};
+	let level = if list.path.is_ident("allow") {
+		LintLevel::Allow
+	} else if list.path.is_ident("expect") {
+		LintLevel::Expect
+	} else if list.path.is_ident("cfg_attr") {
+		// We don't know whether the cfg is active,
+		// so assume the attribute is applied.
+		return list.nested.iter()
+			.skip(1)
+			.find_map(|n| match n {
+				NestedMeta::Meta(m) => lint_level(m, lints),
+				NestedMeta::Lit(_) => None,
+			});
+	} else {
+		return None;
+	};
+	let has_lint = list.nested.iter()
+		.any(|n| matches!(n, NestedMeta::Meta(Meta::Path(p))
+			if lints.iter().any(|lint| p.is_ident(lint))));
+	has_lint.then_some(level)
+}
+
+fn item_attrs(item :&Item) -> &[Attribute] {
+	match item {
+		Item::Const(i) => &i.attrs,
+		Item::Enum(i) => &i.attrs,
+		Item::ExternCrate(i) => &i.attrs,
+		Item::Fn(i) => &i.attrs,
+		Item::ForeignMod(i) => &i.attrs,
+		Item::Impl(i) => &i.attrs,
+		Item::Macro(i) => &i.attrs,
+		Item::Macro2(i) => &i.attrs,
+		Item::Mod(i) => &i.attrs,
+		Item::Static(i) => &i.attrs,
+		Item::Struct(i) => &i.attrs,
+		Item::Trait(i) => &i.attrs,
+		Item::TraitAlias(i) => &i.attrs,
+		Item::Type(i) => &i.attrs,
+		Item::Union(i) => &i.attrs,
+		Item::Use(i) => &i.attrs,
+		_ => &[],
+	}
+}
+
+fn impl_item_attrs(item :&ImplItem) -> &[Attribute] {
+	match item {
+		ImplItem::Const(i) => &i.attrs,
+		ImplItem::Method(i) => &i.attrs,
+		ImplItem::Type(i) => &i.attrs,
+		ImplItem::Macro(i) => &i.attrs,
+		_ => &[],
+	}
+}
+
+fn trait_item_attrs(item :&TraitItem) -> &[Attribute] {
+	match item {
+		TraitItem::Const(i) => &i.attrs,
+		TraitItem::Method(i) => &i.attrs,
+		TraitItem::Type(i) => &i.attrs,
+		TraitItem::Macro(i) => &i.attrs,
+		_ => &[],
+	}
+}
+
+fn imports(tree :&UseTree, prev :Option<&Ident>, public :bool, out :&mut Vec<Import>) {
+	let import = |ident :&Ident, glob| Import { pos : lc(ident.span().start()), glob, public };
+	match tree {
+		UseTree::Path(p) => imports(&p.tree, Some(&p.ident), public, out),
+		UseTree::Name(n) => out.push(import(&n.ident, false)),
+		UseTree::Rename(r) => out.push(import(&r.ident, false)),
+		UseTree::Glob(_) => if let Some(prev) = prev {
+			out.push(import(prev, true));
+		},
+		UseTree::Group(g) => for tree in &g.items {
+			imports(tree, prev, public, out);
+		},
+	}
+}
+
+/// The position of the name that the assignment writes to
+fn assigned_name(expr :&Expr) -> Option<Position> {
+	match expr {
+		Expr::Field(f) => match &f.member {
+			Member::Named(ident) => Some(lc(ident.span().start())),
+			Member::Unnamed(index) => Some(lc(index.span.start())),
+		},
+		Expr::Path(p) => p.path.segments.last()
+			.map(|seg| lc(seg.ident.span().start())),
+		Expr::Paren(p) => assigned_name(&p.expr),
+		Expr::Index(i) => assigned_name(&i.expr),
+		_ => None,
+	}
+}
+
+struct Visitor<'a> {
+	info :&'a mut SourceInfo,
+	/// The lines of the items the suppressions apply to
+	suppression_lines :Vec<usize>,
+}
+
+impl<'a> Visitor<'a> {
+	fn check_suppressions(&mut self, region :Region) {
+		for (suppression, line) in self.info.suppressions.iter_mut().zip(&self.suppression_lines) {
+			// Items are visited outside in, so the outermost item starting on the line is chosen
+			if suppression.region.is_none() && *line == region.0.0 {
+				suppression.region = Some(region);
+			}
+		}
+	}
+	fn check_lint_attrs(&mut self, attrs :&[Attribute], region :Region) {
+		for attr in attrs {
+			let Ok(meta) = attr.parse_meta() else {
+				continue
+			};
+			match lint_level(&meta, DEAD_CODE_LINTS) {
+				Some(LintLevel::Allow) => self.info.allowed.push(region),
+				Some(LintLevel::Expect) => self.info.expectations.push(Expectation {
+					attr : lc(attr.span().start()),
+					region,
+				}),
+				None => (),
+			}
+			if lint_level(&meta, UNUSED_IMPORTS_LINTS).is_some() {
+				self.info.allowed_imports.push(region);
+			}
+		}
+	}
+	fn check_derive_attrs(&mut self, attrs :&[Attribute]) {
+		for attr in attrs.iter().filter(|attr| attr.path.is_ident("derive")) {
+			self.info.derives.push(region(attr.span()));
+			let Ok(Meta::List(list)) = attr.parse_meta() else {
+				continue
+			};
+			for nested in &list.nested {
+				if let NestedMeta::Meta(Meta::Path(path)) = nested {
+					self.info.derive_paths.extend(path.segments.iter()
+						.map(|seg| lc(seg.ident.span().start())));
+				}
+			}
+		}
+	}
+	fn check_test_attrs(&mut self, attrs :&[Attribute], region :Region) {
+		if attrs.iter().any(|attr| is_cfg_test_attr(attr) || is_test_attr(attr)) {
+			self.info.test_regions.push(region);
+		}
+	}
+}
+
+impl<'ast, 'a> Visit<'ast> for Visitor<'a> {
+	fn visit_item(&mut self, i :&'ast Item) {
+		self.check_suppressions(region(i.span()));
+		self.check_lint_attrs(item_attrs(i), region(i.span()));
+		self.check_test_attrs(item_attrs(i), region(i.span()));
+		self.check_derive_attrs(item_attrs(i));
+		visit::visit_item(self, i);
+	}
+	fn visit_impl_item(&mut self, i :&'ast ImplItem) {
+		self.check_suppressions(region(i.span()));
+		self.check_lint_attrs(impl_item_attrs(i), region(i.span()));
+		self.check_test_attrs(impl_item_attrs(i), region(i.span()));
+		visit::visit_impl_item(self, i);
+	}
+	fn visit_trait_item(&mut self, i :&'ast TraitItem) {
+		self.check_suppressions(region(i.span()));
+		self.check_lint_attrs(trait_item_attrs(i), region(i.span()));
+		visit::visit_trait_item(self, i);
+	}
+	fn visit_field(&mut self, i :&'ast Field) {
+		self.check_suppressions(region(i.span()));
+		self.check_lint_attrs(&i.attrs, region(i.span()));
+		visit::visit_field(self, i);
+	}
+	fn visit_variant(&mut self, i :&'ast Variant) {
+		self.check_suppressions(region(i.span()));
+		self.check_lint_attrs(&i.attrs, region(i.span()));
+		visit::visit_variant(self, i);
+	}
+	fn visit_pat(&mut self, i :&'ast Pat) {
+		let path = match i {
+			Pat::Path(p) => Some(&p.path),
+			Pat::TupleStruct(p) => Some(&p.path),
+			Pat::Struct(p) => Some(&p.path),
+			_ => None,
+		};
+		if let Some(seg) = path.and_then(|path| path.segments.last()) {
+			self.info.pattern_paths.push(lc(seg.ident.span().start()));
+		}
+		// Unit variants brought into scope via `use`
+		if let Pat::Ident(p) = i {
+			if p.subpat.is_none() {
+				self.info.pattern_paths.push(lc(p.ident.span().start()));
+			}
+		}
+		visit::visit_pat(self, i);
+	}
+	fn visit_expr_struct(&mut self, i :&'ast ExprStruct) {
+		for field in &i.fields {
+			if let Member::Named(ident) = &field.member {
+				self.info.writes.push(lc(ident.span().start()));
+			}
+		}
+		visit::visit_expr_struct(self, i);
+	}
+	fn visit_expr_assign(&mut self, i :&'ast ExprAssign) {
+		self.info.writes.extend(assigned_name(&i.left));
+		visit::visit_expr_assign(self, i);
+	}
+	fn visit_expr_assign_op(&mut self, i :&'ast ExprAssignOp) {
+		self.info.writes.extend(assigned_name(&i.left));
+		visit::visit_expr_assign_op(self, i);
+	}
+	fn visit_item_mod(&mut self, i :&'ast ItemMod) {
+		if i.content.is_none() {
+			self.info.mod_decls.push(lc(i.ident.span().start()));
+		}
+		visit::visit_item_mod(self, i);
+	}
+	fn visit_item_impl(&mut self, i :&'ast ItemImpl) {
+		self.info.impls.push(Impl {
+			region : region(i.span()),
+			header : (lc(i.span().start()), lc(i.brace_token.span.start())),
+		});
+		if let Some(seg) = i.trait_.as_ref().and_then(|(_bang, path, _for)| path.segments.last()) {
+			self.info.trait_impls.push(TraitImpl {
+				region : region(i.span()),
+				trait_pos : lc(seg.ident.span().start()),
+			});
+		}
+		visit::visit_item_impl(self, i);
+	}
+	fn visit_item_use(&mut self, i :&'ast ItemUse) {
+		let public = matches!(i.vis, syn::Visibility::Public(_));
+		imports(&i.tree, None, public, &mut self.info.imports);
+		visit::visit_item_use(self, i);
+	}
+	fn visit_item_fn(&mut self, i :&'ast ItemFn) {
+		if i.attrs.iter().any(is_test_attr) {
+			self.info.test_fns.push(lc(i.sig.ident.span().start()));
+		}
+		if i.attrs.iter().any(is_export_attr) || has_foreign_abi(&i.sig) {
+			self.info.exported.push(lc(i.sig.ident.span().start()));
+		}
+		self.info.bodies.push(region(i.block.span()));
+		visit::visit_item_fn(self, i);
+	}
+	fn visit_impl_item_method(&mut self, i :&'ast ImplItemMethod) {
+		if i.attrs.iter().any(is_test_attr) {
+			self.info.test_fns.push(lc(i.sig.ident.span().start()));
+		}
+		if i.attrs.iter().any(is_export_attr) || has_foreign_abi(&i.sig) {
+			self.info.exported.push(lc(i.sig.ident.span().start()));
+		}
+		self.info.bodies.push(region(i.block.span()));
+		visit::visit_impl_item_method(self, i);
+	}
+	fn visit_trait_item_method(&mut self, i :&'ast TraitItemMethod) {
+		if let Some(block) = &i.default {
+			self.info.bodies.push(region(block.span()));
+		}
+		visit::visit_trait_item_method(self, i);
+	}
+	fn visit_item_static(&mut self, i :&'ast ItemStatic) {
+		if i.attrs.iter().any(is_export_attr) {
+			self.info.exported.push(lc(i.ident.span().start()));
+		}
+		visit::visit_item_static(self, i);
+	}
+}
+
+pub fn scan_str(file :&str) -> Result<SourceInfo, Error> {
+	let comments = suppression_comments(file);
+	let file = syn::parse_file(file)?;
+	let mut info = SourceInfo {
+		suppressions : comments.iter()
+			.map(|(comment, _line)| Suppression {
+				comment : *comment,
+				region : None,
+			})
+			.collect(),
+		..SourceInfo::default()
+	};
+	let mut visitor = Visitor {
+		info : &mut info,
+		suppression_lines : comments.iter().map(|(_comment, line)| *line).collect(),
+	};
+	// Inner attributes of the file apply to the entire file
+	let whole_file = ((1, 1), (usize::MAX, usize::MAX));
+	visitor.check_lint_attrs(&file.attrs, whole_file);
+	visitor.check_test_attrs(&file.attrs, whole_file);
+	visitor.visit_file(&file);
+	Ok(info)
+}
+
+pub fn scan_file(path :&Path) -> Result<SourceInfo, Error> {
+	let file = std::fs::read_to_string(path)?;
+	scan_str(&file)
+}
+
+/// Lazily scans source files, relative to a prefix
+pub struct SourceCache {
+	prefix :PathBuf,
+	cache :CHashMap<String, Option<Arc<SourceInfo>>>,
+	/// The file and position of the `mod name;` declaration of each out-of-line module's file
+	mod_decls :HashMap<String, (String, Position)>,
+}
+
+impl SourceCache {
+	pub fn new<'a>(prefix :impl Into<&'a Path>) -> Self {
+		Self {
+			prefix : prefix.into().to_owned(),
+			cache : CHashMap::new(),
+			mod_decls : HashMap::new(),
+		}
+	}
+	/// Records that the module in `module_file` is declared via `mod name;` at the position
+	///
+	/// Lint attributes of the declaration, or of the file containing it, apply to the module's file as well.
+	pub fn add_mod_decl(&mut self, module_file :&str, decl_file :&str, pos :Position) {
+		if module_file != decl_file {
+			self.mod_decls.insert(module_file.to_owned(), (decl_file.to_owned(), pos));
+		}
+	}
+	/// Obtains the region in the file, followed by the positions of the
+	/// `mod name;` declarations of the modules the file belongs to
+	pub fn locations<'a>(&'a self, file :&'a str, region :Region) -> Vec<(&'a str, Region)> {
+		let mut locations = vec![(file, region)];
+		while let Some((decl_file, pos)) = self.mod_decls.get(locations[locations.len() - 1].0) {
+			// Guard against cycles, e.g. through `#[path]` attributes
+			if locations.iter().any(|(file, _region)| file == decl_file) {
+				break;
+			}
+			locations.push((decl_file.as_str(), (*pos, *pos)));
+		}
+		locations
+	}
+	/// Obtains the file at the start of the `mod name;` declarations
+	/// leading to the file, like the root of the crate a module belongs to
+	pub fn root_file<'a>(&'a self, file :&'a str) -> &'a str {
+		let locations = self.locations(file, ((1, 1), (1, 1)));
+		locations[locations.len() - 1].0
+	}
+	/// Whether dead code is allowed or expected at the region,
+	/// including by attributes of the modules containing the file
+	pub fn is_allowed(&self, file :&str, region :Region) -> bool {
+		self.locations(file, region).into_iter()
+			.any(|(file, region)| self.get(file).map(|info| info.is_allowed(region)).unwrap_or(false))
+	}
+	/// Whether unused imports are allowed or expected at the region,
+	/// including by attributes of the modules containing the file
+	pub fn is_import_allowed(&self, file :&str, region :Region) -> bool {
+		self.locations(file, region).into_iter()
+			.any(|(file, region)| self.get(file).map(|info| info.is_import_allowed(region)).unwrap_or(false))
+	}
+	/// Obtains the information about the file, or None if it couldn't be scanned
+	pub fn get(&self, file_name :&str) -> Option<Arc<SourceInfo>> {
+		if let Some(info) = self.cache.get(file_name) {
+			return info.clone();
+		}
+		let info = match scan_file(&self.prefix.join(file_name)) {
+			Ok(info) => Some(Arc::new(info)),
+			Err(e) => {
+				warn!("Couldn't scan source file {}: {}", file_name, e);
+				None
+			},
+		};
+		self.cache.insert(file_name.to_owned(), info.clone());
+		info
+	}
+}
@@ -8,5 +8,17 @@ pub fn used_fn() -> u32 {
 }
 
 pub fn unused_fn() -> u32 {
+	only_used_by_unused_fn()
+}
+
+fn only_used_by_unused_fn() -> u32 {
 	42
 }
+
+pub struct OnlyConstructedByUnused;
+
+impl OnlyConstructedByUnused {
+	pub fn new() -> Self {
+		OnlyConstructedByUnused
+	}
+}
@@ -0,0 +1,6 @@
+use library_thing::{count, Counter};
+
+fn main() {
+	let mut counter = Counter::new(1);
+	println!("{}", count(&mut counter));
+}
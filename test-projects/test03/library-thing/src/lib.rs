@@ -0,0 +1,23 @@
+pub struct Counter {
+	pub hits: u32,
+	pub total: u32,
+}
+
+pub static mut WRITE_ONLY_STATIC: u32 = 0;
+
+impl Counter {
+	pub fn new(total: u32) -> Self {
+		Counter {
+			hits: 0,
+			total,
+		}
+	}
+}
+
+pub fn count(counter: &mut Counter) -> u32 {
+	counter.hits += 1;
+	unsafe {
+		WRITE_ONLY_STATIC = counter.total;
+	}
+	counter.total
+}
@@ -0,0 +1,3 @@
+pub fn api_fn() {}
+
+fn unused_private_fn() {}
@@ -0,0 +1,10 @@
+pub mod api;
+mod private;
+
+pub use private::reexported_fn;
+
+pub mod inline {
+	pub fn inline_fn() {}
+
+	fn unused_private_fn() {}
+}
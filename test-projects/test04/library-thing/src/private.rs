@@ -0,0 +1,3 @@
+pub fn reexported_fn() {}
+
+pub fn not_reexported_fn() {}
@@ -0,0 +1,12 @@
+mod module;
+
+use library_thing::{answer, config, Describe};
+
+pub struct InMain;
+
+fn main() {
+	let config = config();
+	let _mode = config.mode;
+	let _in_main = InMain;
+	println!("{} {}", config.describe(), answer() + module::helper());
+}
@@ -0,0 +1,48 @@
+pub trait Describe {
+	fn describe(&self) -> String;
+}
+
+pub struct Config {
+	pub mode: Mode,
+	pub internal: Internal,
+}
+
+/// Named in the signature of a field used from outside, so it has to stay pub
+pub enum Mode {
+	Fast,
+}
+
+pub struct Internal;
+
+impl Describe for Config {
+	fn describe(&self) -> String {
+		match self.mode {
+			Mode::Fast => "fast".to_owned(),
+		}
+	}
+}
+
+pub fn config() -> Config {
+	Config {
+		mode: Mode::Fast,
+		internal: helper(),
+	}
+}
+
+pub fn helper() -> Internal {
+	Internal
+}
+
+mod inner {
+	pub(crate) fn only_in_module() -> u32 {
+		42
+	}
+
+	pub(crate) fn used_in_crate() -> u32 {
+		only_in_module()
+	}
+}
+
+pub fn answer() -> u32 {
+	inner::used_in_crate()
+}
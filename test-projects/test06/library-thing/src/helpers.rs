@@ -0,0 +1,14 @@
+pub fn shared() -> u32 {
+	41
+}
+
+#[cfg(test)]
+pub fn test_only_helper() -> u32 {
+	shared()
+}
+
+#[cfg(test)]
+#[test]
+fn helper_test() {
+	assert_eq!(test_only_helper(), 41);
+}
@@ -0,0 +1,23 @@
+mod helpers;
+
+pub fn used_fn() -> u32 {
+	helpers::shared() + 1
+}
+
+fn only_used_in_tests() -> u32 {
+	helpers::shared()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn test_helper() -> u32 {
+		only_used_in_tests()
+	}
+
+	#[test]
+	fn it_works() {
+		assert_eq!(test_helper(), used_fn() - 1);
+	}
+}
@@ -0,0 +1 @@
+pub fn generated_fn() {}
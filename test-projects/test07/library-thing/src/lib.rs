@@ -0,0 +1,19 @@
+mod generated;
+
+pub struct UnusedStruct;
+
+pub fn unused_fn() {}
+
+pub fn ignored_fn() {}
+
+fn only_used_in_tests() -> u32 {
+	43
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn it_works() {
+		assert_eq!(super::only_used_in_tests(), 43);
+	}
+}
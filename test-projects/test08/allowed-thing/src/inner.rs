@@ -0,0 +1 @@
+fn allowed_in_inner() {}
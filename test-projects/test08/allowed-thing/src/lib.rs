@@ -0,0 +1,5 @@
+#![allow(dead_code)]
+
+mod inner;
+
+fn allowed_in_root() {}
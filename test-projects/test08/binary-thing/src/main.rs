@@ -0,0 +1,6 @@
+use library_thing::{unfulfilled_fn, WithFields};
+
+fn main() {
+	unfulfilled_fn();
+	let _ = std::mem::size_of::<WithFields>();
+}
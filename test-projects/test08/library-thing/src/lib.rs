@@ -0,0 +1,23 @@
+#[allow(dead_code)]
+mod allowed;
+mod plain;
+
+#[allow(dead_code)]
+fn allowed_fn() {}
+
+#[allow(unused)]
+fn allowed_unused_fn() {}
+
+#[expect(dead_code)]
+fn expected_fn() {}
+
+#[expect(dead_code)]
+pub fn unfulfilled_fn() {}
+
+pub struct WithFields {
+	#[allow(dead_code)]
+	allowed_field: u32,
+	unused_field: u32,
+}
+
+fn unused_fn() {}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+mod shapes {
+	pub trait Shape {
+		fn area(&self) -> f64;
+		fn unused_method(&self) -> f64;
+	}
+}
+
+mod other {
+	// Same name as the trait in `shapes`, but never used
+	pub trait Shape {
+		fn area(&self) -> f64;
+	}
+}
+
+trait UsedThroughImpl {
+	fn used_method(&self) -> u32;
+}
+
+struct Square(f64);
+
+struct Circle(f64);
+
+impl shapes::Shape for Square {
+	fn area(&self) -> f64 {
+		self.0 * self.0
+	}
+	fn unused_method(&self) -> f64 {
+		0.0
+	}
+}
+
+impl other::Shape for Circle {
+	fn area(&self) -> f64 {
+		self.0 * self.0 * 3.0
+	}
+}
+
+impl UsedThroughImpl for Square {
+	fn used_method(&self) -> u32 {
+		1
+	}
+}
+
+impl fmt::Display for Square {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "square")
+	}
+}
+
+fn main() {
+	let square = Square(2.0);
+	let _circle = Circle(1.0);
+	println!("{} {}", shapes::Shape::area(&square), square.used_method());
+}
@@ -0,0 +1,12 @@
+#[allow(unused_imports)]
+use library_thing::allowed_import_fn;
+use library_thing::{only_imported_fn, used_fn};
+
+#[allow(dead_code)]
+mod dead_code_allowed {
+	use library_thing::only_imported_fn;
+}
+
+fn main() {
+	println!("{}", used_fn());
+}
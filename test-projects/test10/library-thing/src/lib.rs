@@ -0,0 +1,14 @@
+#[derive(Debug, Clone)]
+pub struct DerivedOnly;
+
+pub fn used_fn() -> u32 {
+	42
+}
+
+pub fn only_imported_fn() -> u32 {
+	42
+}
+
+pub fn allowed_import_fn() -> u32 {
+	42
+}
@@ -0,0 +1,18 @@
+#[derive(macro_thing::Used, Debug)]
+enum Command {
+	Start,
+	Stop,
+	Unused,
+}
+
+fn run(command: &Command) -> &'static str {
+	match command {
+		Command::Start => "start",
+		Command::Stop => "stop",
+		Command::Unused => "unused",
+	}
+}
+
+fn main() {
+	println!("{} {:?}", run(&Command::Start), Command::Start);
+}
@@ -0,0 +1,11 @@
+use proc_macro::TokenStream;
+
+#[proc_macro_derive(Used)]
+pub fn used(_input: TokenStream) -> TokenStream {
+	TokenStream::new()
+}
+
+#[proc_macro_derive(Unused)]
+pub fn unused(_input: TokenStream) -> TokenStream {
+	TokenStream::new()
+}
@@ -0,0 +1,5 @@
+fn main() {
+	println!("{}", library_thing::used_by_other_workspace());
+}
+
+fn unused_in_binary() {}
@@ -0,0 +1,7 @@
+pub fn used_by_other_workspace() -> u32 {
+	42
+}
+
+pub fn unused_fn() -> u32 {
+	42
+}
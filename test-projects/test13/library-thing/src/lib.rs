@@ -0,0 +1,24 @@
+#[no_mangle]
+pub extern "C" fn exported_fn() -> u32 {
+	helper()
+}
+
+#[export_name = "renamed_fn"]
+pub fn exported_under_name() {}
+
+pub extern "C" fn c_abi_fn() {}
+
+#[no_mangle]
+pub static EXPORTED_STATIC: u32 = 42;
+
+fn helper() -> u32 {
+	42
+}
+
+pub mod callbacks {
+	pub fn on_start() {}
+
+	pub fn on_stop() {}
+}
+
+pub fn unused_fn() {}
@@ -0,0 +1,20 @@
+// warnalyzer: ignore
+pub fn ignored_fn() {}
+
+// warnalyzer: ignore only kept for the next release
+
+/// Documented
+pub struct IgnoredWithReason;
+
+pub fn ignored_on_same_line() {} // warnalyzer: ignore
+
+// warnalyzer: ignore
+pub fn used_fn() -> &'static str {
+	"// warnalyzer: ignore"
+}
+
+pub fn entry() -> &'static str {
+	used_fn()
+}
+
+pub fn unused_fn() {}